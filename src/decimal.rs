@@ -0,0 +1,316 @@
+use std::{cmp::Ordering, error::Error, fmt, str::FromStr};
+
+use crate::{
+    error::{ErrorKind, LineIndex, ParseError},
+    float::parse_float,
+    options::{NumberMode, ParseOptions},
+    parse_json_with,
+    types::{JsonValue, Number},
+};
+
+/// A JSON number kept as its exact source text.
+///
+/// Comparisons are exact decimal comparisons, so `1e2`, `100` and `100.0` are equal, while
+/// `0.1` and `0.1000000000000000055511151231257827` are not. Formatting writes the original
+/// text back unchanged.
+#[derive(Debug, Clone)]
+pub struct Decimal {
+    lexeme: String,
+}
+
+/// The value of a decimal as `0.digits × 10^exponent`, without leading or trailing zeros.
+struct Normalized {
+    negative: bool,
+    digits: Vec<u8>,
+    exponent: i64,
+}
+
+/// Error returned when a [`Decimal`] cannot be converted to a primitive number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConversionError {
+    target: &'static str,
+}
+
+impl fmt::Display for ConversionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "number cannot be represented as {}", self.target)
+    }
+}
+
+impl Error for ConversionError {}
+
+impl Decimal {
+    /// Wraps text that is already known to follow the JSON number grammar.
+    pub(crate) fn from_lexeme(lexeme: &str) -> Self {
+        Decimal {
+            lexeme: lexeme.to_string(),
+        }
+    }
+
+    /// The number exactly as it was written.
+    pub fn as_str(&self) -> &str {
+        &self.lexeme
+    }
+
+    /// Converts to the nearest `f64`, failing if the number is beyond the `f64` range.
+    ///
+    /// Numbers too small to represent round to zero, like any other decimal to float conversion.
+    pub fn to_f64(&self) -> Result<f64, ConversionError> {
+        match parse_float(&self.lexeme) {
+            f if f.is_finite() => Ok(f),
+            _ => Err(ConversionError { target: "f64" }),
+        }
+    }
+
+    /// Converts to an `f64` only if no rounding is needed.
+    pub fn to_f64_exact(&self) -> Result<f64, ConversionError> {
+        let f = self.to_f64()?;
+        // every finite f64 has a decimal expansion of at most 767 significant digits
+        let expansion = Decimal::from_lexeme(&format!("{:.800e}", f));
+
+        match expansion == *self {
+            true => Ok(f),
+            false => Err(ConversionError { target: "f64" }),
+        }
+    }
+
+    /// Converts to an `i64`, failing unless the number is an integer in the `i64` range.
+    pub fn to_i64(&self) -> Result<i64, ConversionError> {
+        self.integer_text()
+            .and_then(|text| text.parse::<i64>().ok())
+            .ok_or(ConversionError { target: "i64" })
+    }
+
+    /// Converts to a `u64`, failing unless the number is an integer in the `u64` range.
+    pub fn to_u64(&self) -> Result<u64, ConversionError> {
+        self.integer_text()
+            .and_then(|text| text.parse::<u64>().ok())
+            .ok_or(ConversionError { target: "u64" })
+    }
+
+    pub fn is_integer(&self) -> bool {
+        let normalized = self.normalize();
+        normalized.exponent >= normalized.digits.len() as i64
+    }
+
+    /// The plain integer digits of an integral value, if they are short enough to be worth parsing.
+    fn integer_text(&self) -> Option<String> {
+        let normalized = self.normalize();
+        let zeros = normalized.exponent - normalized.digits.len() as i64;
+        // 20 digits are enough for every 64-bit integer
+        if zeros < 0 || normalized.exponent > 20 {
+            return None;
+        }
+
+        let mut text = String::with_capacity(normalized.exponent as usize + 1);
+        if normalized.negative {
+            text.push('-');
+        }
+        text.extend(normalized.digits.iter().map(|&d| (b'0' + d) as char));
+        text.extend(std::iter::repeat_n('0', zeros as usize));
+        if normalized.digits.is_empty() {
+            text.push('0');
+        }
+
+        Some(text)
+    }
+
+    fn normalize(&self) -> Normalized {
+        let (negative, rest) = match self.lexeme.strip_prefix('-') {
+            Some(rest) => (true, rest),
+            None => (false, self.lexeme.as_str()),
+        };
+        let (mantissa, exponent) = match rest.find(['e', 'E']) {
+            Some(index) => (&rest[..index], parse_exponent(&rest[index + 1..])),
+            None => (rest, 0),
+        };
+        let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+        let mut digits: Vec<u8> = integer
+            .bytes()
+            .chain(fraction.bytes())
+            .map(|b| b - b'0')
+            .collect();
+        let mut exponent = exponent.saturating_add(integer.len() as i64);
+
+        let leading_zeros = digits.iter().take_while(|&&d| d == 0).count();
+        digits.drain(..leading_zeros);
+        exponent = exponent.saturating_sub(leading_zeros as i64);
+        while digits.last() == Some(&0) {
+            digits.pop();
+        }
+
+        if digits.is_empty() {
+            // all zeros compare equal, whatever their sign or exponent
+            return Normalized {
+                negative: false,
+                digits,
+                exponent: 0,
+            };
+        }
+
+        Normalized {
+            negative,
+            digits,
+            exponent,
+        }
+    }
+}
+
+/// Parses an exponent, saturating instead of overflowing for absurdly long ones.
+pub(crate) fn parse_exponent(text: &str) -> i64 {
+    let (negative, digits) = match text.as_bytes().first() {
+        Some(b'-') => (true, &text[1..]),
+        Some(b'+') => (false, &text[1..]),
+        _ => (false, text),
+    };
+    let magnitude = digits
+        .bytes()
+        .fold(0i64, |acc, b| {
+            acc.saturating_mul(10).saturating_add((b - b'0') as i64)
+        })
+        // keep room for the digit count that is added on top
+        .min(i64::MAX / 2);
+
+    if negative {
+        -magnitude
+    } else {
+        magnitude
+    }
+}
+
+impl PartialEq for Decimal {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Decimal {}
+
+impl PartialOrd for Decimal {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Decimal {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let (a, b) = (self.normalize(), other.normalize());
+        let sign = |n: &Normalized| match (n.digits.is_empty(), n.negative) {
+            (true, _) => 0,
+            (false, true) => -1,
+            (false, false) => 1,
+        };
+
+        let magnitude = || {
+            a.exponent
+                .cmp(&b.exponent)
+                .then_with(|| a.digits.cmp(&b.digits))
+        };
+
+        match sign(&a).cmp(&sign(&b)) {
+            Ordering::Equal if sign(&a) == 0 => Ordering::Equal,
+            Ordering::Equal if a.negative => magnitude().reverse(),
+            Ordering::Equal => magnitude(),
+            unequal => unequal,
+        }
+    }
+}
+
+impl fmt::Display for Decimal {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.lexeme)
+    }
+}
+
+impl FromStr for Decimal {
+    type Err = ParseError;
+
+    /// Parses text following the JSON number grammar, e.g. `-12.5e3`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let options = ParseOptions {
+            number_mode: NumberMode::Exact,
+            ..ParseOptions::default()
+        };
+
+        match parse_json_with(s, &options)? {
+            // surrounding whitespace is not part of a number
+            JsonValue::Number(Number::Decimal(decimal)) if decimal.lexeme.len() == s.len() => {
+                Ok(decimal)
+            }
+            _ => Err(ParseError::new(
+                ErrorKind::InvalidNumber,
+                0..s.len(),
+                &LineIndex::new(s),
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn decimal(s: &str) -> Decimal {
+        s.parse()
+            .unwrap_or_else(|e| panic!("Expect {} to be a valid decimal: {}", s, e))
+    }
+
+    #[test]
+    fn test_decimal_keeps_lexeme() {
+        for input in [
+            "0.1000000000000000055511151231257827",
+            "1e400",
+            "-0.0E+00",
+            "12",
+        ] {
+            assert_eq!(decimal(input).to_string(), input);
+        }
+    }
+
+    #[test]
+    fn test_decimal_exact_comparison() {
+        assert_eq!(decimal("1e2"), decimal("100"));
+        assert_eq!(decimal("100.000"), decimal("0.1e3"));
+        assert_eq!(decimal("-0"), decimal("0.0e10"));
+        assert_ne!(
+            decimal("0.1"),
+            decimal("0.1000000000000000055511151231257827")
+        );
+        assert!(decimal("0.1") < decimal("0.1000000000000000055511151231257827"));
+        assert!(decimal("-2") < decimal("-1.5"));
+        assert!(decimal("-1e-400") < decimal("0"));
+        assert!(decimal("1e400") > decimal("9e399"));
+    }
+
+    #[test]
+    fn test_decimal_to_f64() {
+        assert_eq!(decimal("2.5e-3").to_f64(), Ok(0.0025));
+        assert!(decimal("1e400").to_f64().is_err());
+        assert!(decimal("0.1").to_f64_exact().is_err());
+        assert_eq!(
+            decimal("0.1000000000000000055511151231257827021181583404541015625").to_f64_exact(),
+            Ok(0.1)
+        );
+        assert_eq!(decimal("0.5").to_f64_exact(), Ok(0.5));
+    }
+
+    #[test]
+    fn test_decimal_to_integers() {
+        assert_eq!(decimal("1e2").to_i64(), Ok(100));
+        assert_eq!(decimal("-9223372036854775808").to_i64(), Ok(i64::MIN));
+        assert!(decimal("9223372036854775808").to_i64().is_err());
+        assert_eq!(decimal("18446744073709551615").to_u64(), Ok(u64::MAX));
+        assert!(decimal("-1").to_u64().is_err());
+        assert!(decimal("1.5").to_i64().is_err());
+        assert!(decimal("1e400").to_i64().is_err());
+        assert_eq!(decimal("-0.0").to_i64(), Ok(0));
+    }
+
+    #[test]
+    fn test_decimal_from_str_rejects_invalid_numbers() {
+        for input in ["01", " 1", "1.", "abc", "[1]"] {
+            assert!(input.parse::<Decimal>().is_err(), "{}", input);
+        }
+    }
+}
@@ -0,0 +1,325 @@
+use std::fmt;
+
+use crate::error::ParseError;
+
+const RED: &str = "\x1b[1;31m";
+const BLUE: &str = "\x1b[1;34m";
+const CYAN: &str = "\x1b[1;36m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+/// Spans covering more lines than this only show their first and last lines.
+const MAX_LINES: usize = 4;
+
+/// A [`ParseError`] rendered against its source text, in the style of rustc:
+///
+/// ```text
+/// error: Unexpected token, expected value
+///  --> line 1, column 7
+///   |
+/// 1 | [1, 2,]
+///   |       ^
+///   = hint: trailing comma not allowed
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Diagnostic<'a> {
+    error: &'a ParseError,
+    source: &'a str,
+    color: bool,
+}
+
+impl ParseError {
+    /// Renders this error against the `source` it was produced from.
+    pub fn diagnostic<'a>(&'a self, source: &'a str) -> Diagnostic<'a> {
+        Diagnostic {
+            error: self,
+            source,
+            color: false,
+        }
+    }
+}
+
+impl<'a> Diagnostic<'a> {
+    /// Enables or disables ANSI colouring of the output.
+    pub fn with_color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    fn paint(&self, style: &'static str) -> &'static str {
+        if self.color {
+            style
+        } else {
+            ""
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (red, blue, cyan, bold, reset) = (
+            self.paint(RED),
+            self.paint(BLUE),
+            self.paint(CYAN),
+            self.paint(BOLD),
+            self.paint(RESET),
+        );
+        let error = self.error;
+
+        write!(f, "{}error{}{}: ", red, reset, bold)?;
+        error.fmt_message(f)?;
+        writeln!(f, "{}", reset)?;
+
+        let lines = source_lines(self.source, error.span().start, error.span().end);
+        let gutter_width = lines.last().map_or(1, |line| line.number.to_string().len());
+        let gutter = " ".repeat(gutter_width);
+
+        writeln!(
+            f,
+            "{}{}-->{} line {}, column {}",
+            gutter,
+            blue,
+            reset,
+            error.line(),
+            error.column()
+        )?;
+        writeln!(f, "{} {}|{}", gutter, blue, reset)?;
+
+        for (index, line) in lines.iter().enumerate() {
+            if lines.len() > MAX_LINES && index == 1 {
+                writeln!(f, "{}{}...{}", gutter, blue, reset)?;
+            }
+            if lines.len() > MAX_LINES && index > 0 && index < lines.len() - 1 {
+                continue;
+            }
+
+            writeln!(
+                f,
+                "{}{:>width$} |{} {}",
+                blue,
+                line.number,
+                reset,
+                line.text,
+                width = gutter_width
+            )?;
+
+            // keep tabs in the padding so the markers line up with the source above
+            let padding: String = line.text[..line.marker_start]
+                .chars()
+                .map(|c| if c == '\t' { '\t' } else { ' ' })
+                .collect();
+            let marker_width = line.text[line.marker_start..line.marker_end]
+                .chars()
+                .count()
+                .max(1);
+            let marker = if index == 0 { "^" } else { "~" };
+
+            writeln!(
+                f,
+                "{} {}|{} {}{}{}{}",
+                gutter,
+                blue,
+                reset,
+                padding,
+                red,
+                marker.repeat(marker_width),
+                reset
+            )?;
+        }
+
+        if let Some(hint) = error.hint() {
+            writeln!(
+                f,
+                "{} {}={} {}hint{}: {}",
+                gutter, blue, reset, cyan, reset, hint
+            )?;
+        }
+        if let Some((line, column)) = error.related_position() {
+            writeln!(
+                f,
+                "{} {}={} {}note{}: first seen at line {}, column {}",
+                gutter, blue, reset, cyan, reset, line, column
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+struct SourceLine<'s> {
+    number: usize,
+    text: &'s str,
+    marker_start: usize,
+    marker_end: usize,
+}
+
+/// Splits out the lines touched by the byte range `start..end`, with the marked part of each line.
+fn source_lines(source: &str, start: usize, end: usize) -> Vec<SourceLine<'_>> {
+    let start = start.min(source.len());
+    let end = end.clamp(start, source.len());
+    let mut lines = Vec::new();
+    let mut line_start = source[..start].rfind('\n').map_or(0, |i| i + 1);
+    let mut number = source[..line_start].matches('\n').count() + 1;
+
+    loop {
+        let line_end = source[line_start..]
+            .find('\n')
+            .map_or(source.len(), |i| line_start + i);
+        let text = source[line_start..line_end].trim_end_matches('\r');
+        let marker_start = start.max(line_start) - line_start;
+        let marker_end =
+            (end.min(line_end) - line_start).clamp(marker_start, text.len().max(marker_start));
+
+        lines.push(SourceLine {
+            number,
+            text,
+            marker_start: marker_start.min(text.len()),
+            marker_end: marker_end.min(text.len()),
+        });
+
+        // a span ending right after the line break does not reach into the next line
+        if end <= line_end + 1 || line_end == source.len() {
+            return lines;
+        }
+
+        line_start = line_end + 1;
+        number += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_json, parse_json_with, DuplicateKeyPolicy, ParseOptions};
+
+    fn render(input: &str) -> String {
+        match parse_json(input) {
+            Ok(_) => panic!("Expect error for {}", input),
+            Err(err) => err.diagnostic(input).to_string(),
+        }
+    }
+
+    #[test]
+    fn test_render_trailing_comma() {
+        let expected = "\
+error: Unexpected token, expected value
+ --> line 1, column 7
+  |
+1 | [1, 2,]
+  |       ^
+  = hint: trailing comma not allowed
+";
+
+        assert_eq!(render("[1, 2,]"), expected);
+    }
+
+    #[test]
+    fn test_render_single_quoted_string_on_later_line() {
+        let input = "{\n\t\"name\": 'nail'\n}";
+        let expected = "\
+error: Unexpected character, expected value
+ --> line 2, column 10
+  |
+2 | \t\"name\": 'nail'
+  | \t        ^
+  = hint: strings must use double quotes
+";
+
+        assert_eq!(render(input), expected);
+    }
+
+    #[test]
+    fn test_render_invalid_number_span() {
+        let expected = "\
+error: Invalid number
+ --> line 1, column 9
+  |
+1 | {\"id\": [007]}
+  |         ^^
+  = hint: leading zeros are not allowed
+";
+
+        assert_eq!(render("{\"id\": [007]}"), expected);
+    }
+
+    #[test]
+    fn test_render_unescaped_newline() {
+        let input = "[\"abc\ndef\"]";
+        let rendered = render(input);
+
+        assert!(rendered.ends_with("1 | [\"abc\n  |      ^\n  = hint: strings cannot span multiple lines, use \\n instead\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn test_source_lines_across_lines() {
+        let lines = source_lines("ab\ncdef\ngh", 1, 6);
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(
+            (
+                lines[0].number,
+                lines[0].text,
+                lines[0].marker_start,
+                lines[0].marker_end
+            ),
+            (1, "ab", 1, 2)
+        );
+        assert_eq!(
+            (
+                lines[1].number,
+                lines[1].text,
+                lines[1].marker_start,
+                lines[1].marker_end
+            ),
+            (2, "cdef", 0, 3)
+        );
+    }
+
+    #[test]
+    fn test_render_end_of_input() {
+        let rendered = render("{\"a\": ");
+
+        assert!(
+            rendered.contains("1 | {\"a\": \n  |       ^\n"),
+            "{}",
+            rendered
+        );
+    }
+
+    #[test]
+    fn test_render_with_color() {
+        let input = "[1 2]";
+        let rendered = parse_json(input)
+            .unwrap_err()
+            .diagnostic(input)
+            .with_color(true)
+            .to_string();
+
+        assert!(rendered.starts_with("\x1b[1;31merror\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;31m^\x1b[0m"));
+    }
+
+    #[test]
+    fn test_render_duplicate_key_note() {
+        let input = "{\"a\": 1,\n \"a\": 2}";
+        let options = ParseOptions {
+            duplicate_keys: DuplicateKeyPolicy::Error,
+            ..ParseOptions::default()
+        };
+        let rendered = parse_json_with(input, &options)
+            .unwrap_err()
+            .diagnostic(input)
+            .to_string();
+        let expected = "\
+error: Duplicate object key
+ --> line 2, column 2
+  |
+2 |  \"a\": 2}
+  |  ^^^
+  = hint: each key may only appear once in an object
+  = note: first seen at line 1, column 2
+";
+
+        assert_eq!(rendered, expected);
+    }
+}
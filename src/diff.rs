@@ -0,0 +1,639 @@
+use std::cmp::Ordering;
+
+use crate::{
+    patch::{Patch, PatchOperation},
+    pointer::JsonPointer,
+    types::JsonValue,
+};
+
+/// Largest product of array lengths aligned by [`ArrayStrategy::Lcs`], which keeps its table
+/// at about 16 MB.
+const MAX_LCS_CELLS: usize = 4_000_000;
+
+/// How the elements of two arrays are paired up before being compared.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub enum ArrayStrategy {
+    /// Elements at the same position are compared; extra elements are added or removed at the
+    /// end.
+    #[default]
+    Index,
+    /// Elements are aligned along their longest common subsequence, so an insertion in the
+    /// middle is reported as one addition rather than a change of every following element.
+    ///
+    /// This takes time and memory proportional to the product of the lengths of the arrays,
+    /// once equal leading and trailing elements are set aside. When that product exceeds
+    /// 4,000,000, the remaining elements are compared as with [`Index`](Self::Index) instead.
+    Lcs,
+    /// Objects with equal values of this member, such as `"id"`, are paired wherever they are,
+    /// and reordering is reported as moves. Elements without the member pair with equal ones.
+    Key(String),
+}
+
+/// Options for [`diff_with`](crate::diff_with).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffOptions {
+    pub arrays: ArrayStrategy,
+    /// Numbers that differ by at most this much are considered equal. Integers and floats
+    /// always compare by value, so `1` equals `1.0` even without a tolerance.
+    pub float_tolerance: f64,
+}
+
+/// One difference between two values.
+///
+/// Paths point into the value as it is while the changes are applied one after the other, so
+/// in arrays they account for the earlier additions, removals and moves of the same array.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Added {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    Removed {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    Changed {
+        path: JsonPointer,
+        from: JsonValue,
+        to: JsonValue,
+    },
+    /// An array element moved, only reported with [`ArrayStrategy::Key`].
+    Moved {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+}
+
+/// The differences between two values, see [`diff`](crate::diff).
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Diff {
+    changes: Vec<Change>,
+}
+
+impl Change {
+    /// Where the change happens; for a move, where the element ends up.
+    pub fn path(&self) -> &JsonPointer {
+        match self {
+            Change::Added { path, .. }
+            | Change::Removed { path, .. }
+            | Change::Changed { path, .. }
+            | Change::Moved { path, .. } => path,
+        }
+    }
+}
+
+impl Diff {
+    pub fn changes(&self) -> &[Change] {
+        &self.changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The changes as a JSON Patch that turns the first value into the second one, up to the
+    /// float tolerance.
+    pub fn to_patch(&self) -> Patch {
+        self.changes
+            .iter()
+            .map(|change| match change.clone() {
+                Change::Added { path, value } => PatchOperation::Add { path, value },
+                Change::Removed { path, .. } => PatchOperation::Remove { path },
+                Change::Changed { path, to, .. } => PatchOperation::Replace { path, value: to },
+                Change::Moved { from, path } => PatchOperation::Move { from, path },
+            })
+            .collect()
+    }
+}
+
+impl IntoIterator for Diff {
+    type Item = Change;
+    type IntoIter = std::vec::IntoIter<Change>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.changes.into_iter()
+    }
+}
+
+/// Compares two values, pairing array elements by position.
+pub fn diff(from: &JsonValue, to: &JsonValue) -> Diff {
+    diff_with(from, to, &DiffOptions::default())
+}
+
+/// Same as [`diff`], with explicit [`DiffOptions`].
+pub fn diff_with(from: &JsonValue, to: &JsonValue, options: &DiffOptions) -> Diff {
+    let mut differ = Differ {
+        options,
+        changes: Vec::new(),
+    };
+    differ.values(from, to, &JsonPointer::root());
+
+    Diff {
+        changes: differ.changes,
+    }
+}
+
+/// How one array becomes another: elements compared with each other, removed or added.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Edit {
+    Pair(usize, usize),
+    Remove(usize),
+    Add(usize),
+}
+
+struct Differ<'o> {
+    options: &'o DiffOptions,
+    changes: Vec<Change>,
+}
+
+impl Differ<'_> {
+    fn values(&mut self, from: &JsonValue, to: &JsonValue, path: &JsonPointer) {
+        match (from, to) {
+            (JsonValue::Object(from), JsonValue::Object(to)) => {
+                for (key, old) in from {
+                    let child = child_path(path, key);
+                    match to.get(key) {
+                        Some(new) => self.values(old, new, &child),
+                        None => self.changes.push(Change::Removed {
+                            path: child,
+                            value: old.clone(),
+                        }),
+                    }
+                }
+                for (key, new) in to {
+                    if !from.contains_key(key) {
+                        self.changes.push(Change::Added {
+                            path: child_path(path, key),
+                            value: new.clone(),
+                        });
+                    }
+                }
+            }
+            (JsonValue::Array(from), JsonValue::Array(to)) => match &self.options.arrays {
+                ArrayStrategy::Index => {
+                    let common = from.len().min(to.len());
+                    let edits = (0..common)
+                        .map(|i| Edit::Pair(i, i))
+                        .chain((common..from.len()).map(Edit::Remove))
+                        .chain((common..to.len()).map(Edit::Add));
+                    self.apply_edits(from, to, edits.collect(), path);
+                }
+                ArrayStrategy::Lcs => {
+                    let edits = self.lcs_edits(from, to);
+                    self.apply_edits(from, to, edits, path);
+                }
+                ArrayStrategy::Key(key) => self.keyed_arrays(from, to, key, path),
+            },
+            _ if self.equal(from, to) => {}
+            _ => self.changes.push(Change::Changed {
+                path: path.clone(),
+                from: from.clone(),
+                to: to.clone(),
+            }),
+        }
+    }
+
+    /// Equality up to the float tolerance, with object members in any order.
+    fn equal(&self, from: &JsonValue, to: &JsonValue) -> bool {
+        match (from, to) {
+            (JsonValue::Number(from), JsonValue::Number(to)) => {
+                from.value_cmp(to) == Some(Ordering::Equal)
+                    || (self.options.float_tolerance > 0.0
+                        && (from.as_f64_lossy() - to.as_f64_lossy()).abs()
+                            <= self.options.float_tolerance)
+            }
+            (JsonValue::Array(from), JsonValue::Array(to)) => {
+                from.len() == to.len() && from.iter().zip(to).all(|(from, to)| self.equal(from, to))
+            }
+            (JsonValue::Object(from), JsonValue::Object(to)) => {
+                from.len() == to.len()
+                    && from
+                        .iter()
+                        .all(|(key, from)| to.get(key).is_some_and(|to| self.equal(from, to)))
+            }
+            _ => from == to,
+        }
+    }
+
+    /// Aligns the arrays along their longest common subsequence of equal elements. A removal
+    /// followed by an addition is left for [`apply_edits`](Self::apply_edits) to pair up.
+    fn lcs_edits(&self, from: &[JsonValue], to: &[JsonValue]) -> Vec<Edit> {
+        let prefix = from
+            .iter()
+            .zip(to)
+            .take_while(|(from, to)| self.equal(from, to))
+            .count();
+        let suffix = from[prefix..]
+            .iter()
+            .rev()
+            .zip(to[prefix..].iter().rev())
+            .take_while(|(from, to)| self.equal(from, to))
+            .count();
+        let (n, m) = (from.len() - prefix - suffix, to.len() - prefix - suffix);
+
+        let mut edits: Vec<Edit> = (0..prefix).map(|i| Edit::Pair(i, i)).collect();
+        if n.saturating_mul(m) > MAX_LCS_CELLS {
+            // too large to align, removals and additions in one run are paired up by position
+            edits.extend((prefix..prefix + n).map(Edit::Remove));
+            edits.extend((prefix..prefix + m).map(Edit::Add));
+            edits.extend((0..suffix).map(|k| Edit::Pair(prefix + n + k, prefix + m + k)));
+            return edits;
+        }
+
+        // lengths[i][j] is the length of the LCS of the middle parts from i and j onwards
+        let mut lengths = vec![vec![0u32; m + 1]; n + 1];
+        for i in (0..n).rev() {
+            for j in (0..m).rev() {
+                lengths[i][j] = match self.equal(&from[prefix + i], &to[prefix + j]) {
+                    true => lengths[i + 1][j + 1] + 1,
+                    false => lengths[i + 1][j].max(lengths[i][j + 1]),
+                };
+            }
+        }
+
+        let (mut i, mut j) = (0, 0);
+        while i < n || j < m {
+            if i < n && j < m && self.equal(&from[prefix + i], &to[prefix + j]) {
+                edits.push(Edit::Pair(prefix + i, prefix + j));
+                i += 1;
+                j += 1;
+            } else if j == m || (i < n && lengths[i + 1][j] >= lengths[i][j + 1]) {
+                edits.push(Edit::Remove(prefix + i));
+                i += 1;
+            } else {
+                edits.push(Edit::Add(prefix + j));
+                j += 1;
+            }
+        }
+        edits.extend((0..suffix).map(|k| Edit::Pair(prefix + n + k, prefix + m + k)));
+
+        edits
+    }
+
+    /// Turns edits into changes. Within each run of removals and additions, removed and added
+    /// elements are paired and compared in place, then the extra removals happen from the last
+    /// one backwards and the extra additions in order.
+    fn apply_edits(
+        &mut self,
+        from: &[JsonValue],
+        to: &[JsonValue],
+        edits: Vec<Edit>,
+        path: &JsonPointer,
+    ) {
+        // position in the array as it is being changed
+        let mut position = 0;
+        let mut edits = edits.into_iter().peekable();
+
+        while let Some(edit) = edits.next() {
+            let (mut removed, mut added) = (Vec::new(), Vec::new());
+            match edit {
+                Edit::Pair(i, j) => {
+                    self.values(&from[i], &to[j], &child_path(path, &position.to_string()));
+                    position += 1;
+                    continue;
+                }
+                Edit::Remove(i) => removed.push(i),
+                Edit::Add(j) => added.push(j),
+            }
+            while let Some(edit) = edits.next_if(|edit| !matches!(edit, Edit::Pair(..))) {
+                match edit {
+                    Edit::Remove(i) => removed.push(i),
+                    Edit::Add(j) => added.push(j),
+                    Edit::Pair(..) => {}
+                }
+            }
+
+            let paired = removed.len().min(added.len());
+            for (&i, &j) in removed.iter().zip(&added) {
+                self.values(&from[i], &to[j], &child_path(path, &position.to_string()));
+                position += 1;
+            }
+            for (offset, &i) in removed[paired..].iter().enumerate().rev() {
+                self.changes.push(Change::Removed {
+                    path: child_path(path, &(position + offset).to_string()),
+                    value: from[i].clone(),
+                });
+            }
+            for &j in &added[paired..] {
+                self.changes.push(Change::Added {
+                    path: child_path(path, &position.to_string()),
+                    value: to[j].clone(),
+                });
+                position += 1;
+            }
+        }
+    }
+
+    /// Pairs elements by the value of their `key` member, then removes the unpaired ones, and
+    /// adds and moves elements into their final order from the first position onwards.
+    fn keyed_arrays(
+        &mut self,
+        from: &[JsonValue],
+        to: &[JsonValue],
+        key: &str,
+        path: &JsonPointer,
+    ) {
+        let mut paired = vec![false; from.len()];
+        let partners: Vec<Option<usize>> = to
+            .iter()
+            .map(|new| {
+                let partner = (0..from.len()).find(|&i| {
+                    !paired[i]
+                        && match (from[i].get(key), new.get(key)) {
+                            (Some(old_id), Some(new_id)) => old_id.value_eq(new_id),
+                            (None, None) => self.equal(&from[i], new),
+                            _ => false,
+                        }
+                });
+                if let Some(i) = partner {
+                    paired[i] = true;
+                }
+                partner
+            })
+            .collect();
+
+        for i in (0..from.len()).rev().filter(|&i| !paired[i]) {
+            self.changes.push(Change::Removed {
+                path: child_path(path, &i.to_string()),
+                value: from[i].clone(),
+            });
+        }
+
+        // the elements of `from` still in the array, in their current order
+        let mut current: Vec<Option<usize>> =
+            (0..from.len()).filter(|&i| paired[i]).map(Some).collect();
+        for (j, partner) in partners.into_iter().enumerate() {
+            let target = child_path(path, &j.to_string());
+            let i = match partner {
+                Some(i) => i,
+                None => {
+                    self.changes.push(Change::Added {
+                        path: target,
+                        value: to[j].clone(),
+                    });
+                    current.insert(j, None);
+                    continue;
+                }
+            };
+
+            // everything before `j` is in place already, so the element is at `j` or after it
+            let k = current
+                .iter()
+                .position(|&element| element == Some(i))
+                .unwrap_or(j);
+            if k != j {
+                self.changes.push(Change::Moved {
+                    from: child_path(path, &k.to_string()),
+                    path: target.clone(),
+                });
+                let element = current.remove(k);
+                current.insert(j, element);
+            }
+            self.values(&from[i], &to[j], &target);
+        }
+    }
+}
+
+fn child_path(path: &JsonPointer, token: &str) -> JsonPointer {
+    let mut child = path.clone();
+    child.push(token);
+    child
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{json, parse_json_with, NumberMode, ParseOptions};
+
+    fn pointer(text: &str) -> JsonPointer {
+        JsonPointer::parse(text).unwrap()
+    }
+
+    fn with_arrays(arrays: ArrayStrategy) -> DiffOptions {
+        DiffOptions {
+            arrays,
+            ..DiffOptions::default()
+        }
+    }
+
+    /// Checks that the patch of the diff turns `from` into `to`.
+    fn assert_patch_round_trips(from: &JsonValue, to: &JsonValue, options: &DiffOptions) {
+        let mut patched = from.clone();
+        patched
+            .apply_patch(&diff_with(from, to, options).to_patch())
+            .unwrap();
+        assert_eq!(&patched, to, "{:?}", options.arrays);
+    }
+
+    #[test]
+    fn test_object_changes() {
+        let from = json!({"name": "api", "replicas": 2, "env": {"LOG": "info", "OLD": "1"}});
+        let to = json!({"name": "api", "replicas": 3, "env": {"LOG": "info", "NEW": "2"}});
+
+        assert_eq!(
+            diff(&from, &to).changes(),
+            &[
+                Change::Changed {
+                    path: pointer("/replicas"),
+                    from: json!(2),
+                    to: json!(3),
+                },
+                Change::Removed {
+                    path: pointer("/env/OLD"),
+                    value: json!("1"),
+                },
+                Change::Added {
+                    path: pointer("/env/NEW"),
+                    value: json!("2"),
+                },
+            ]
+        );
+        assert!(diff(&from, &from).is_empty());
+        assert!(diff(&json!({"a": 1, "b": 2}), &json!({"b": 2.0, "a": 1})).is_empty());
+    }
+
+    #[test]
+    fn test_index_strategy() {
+        let from = json!([1, 2, 3, 4]);
+        let to = json!([1, 5]);
+
+        assert_eq!(
+            diff(&from, &to).changes(),
+            &[
+                Change::Changed {
+                    path: pointer("/1"),
+                    from: json!(2),
+                    to: json!(5),
+                },
+                Change::Removed {
+                    path: pointer("/3"),
+                    value: json!(4),
+                },
+                Change::Removed {
+                    path: pointer("/2"),
+                    value: json!(3),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lcs_strategy() {
+        let options = with_arrays(ArrayStrategy::Lcs);
+        let from = json!(["a", "b", "c", "d"]);
+        let to = json!(["a", "x", "b", "d", "e"]);
+
+        assert_eq!(
+            diff_with(&from, &to, &options).changes(),
+            &[
+                Change::Added {
+                    path: pointer("/1"),
+                    value: json!("x"),
+                },
+                Change::Removed {
+                    path: pointer("/3"),
+                    value: json!("c"),
+                },
+                Change::Added {
+                    path: pointer("/4"),
+                    value: json!("e"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lcs_strategy_on_large_arrays() {
+        let from = JsonValue::from((0..10_000).map(|i| json!({"id": i})).collect::<Vec<_>>());
+        let mut to = from.clone();
+        to[0]["id"] = json!(-1);
+        to[9_999]["id"] = json!(-2);
+        const { assert!(10_000 * 10_000 > MAX_LCS_CELLS) };
+
+        let changes = diff_with(&from, &to, &with_arrays(ArrayStrategy::Lcs));
+        assert_eq!(
+            changes,
+            diff_with(&from, &to, &with_arrays(ArrayStrategy::Index))
+        );
+        assert_eq!(changes.changes().len(), 2);
+        assert_patch_round_trips(&from, &to, &with_arrays(ArrayStrategy::Lcs));
+    }
+
+    #[test]
+    fn test_key_strategy() {
+        let options = with_arrays(ArrayStrategy::Key("id".to_string()));
+        let from = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]);
+        let to = json!([{"id": 3, "v": "c"}, {"id": 4, "v": "d"}, {"id": 1, "v": "z"}]);
+
+        assert_eq!(
+            diff_with(&from, &to, &options).changes(),
+            &[
+                Change::Removed {
+                    path: pointer("/1"),
+                    value: json!({"id": 2, "v": "b"}),
+                },
+                Change::Moved {
+                    from: pointer("/1"),
+                    path: pointer("/0"),
+                },
+                Change::Added {
+                    path: pointer("/1"),
+                    value: json!({"id": 4, "v": "d"}),
+                },
+                Change::Changed {
+                    path: pointer("/2/v"),
+                    from: json!("a"),
+                    to: json!("z"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_float_tolerance() {
+        let from = json!({"cpu": 0.5, "mem": 1.0, "ratio": 0.1});
+        let to = json!({"cpu": 0.5000001, "mem": 1, "ratio": 0.2});
+        let options = DiffOptions {
+            float_tolerance: 1e-6,
+            ..DiffOptions::default()
+        };
+
+        assert_eq!(diff(&from, &to).changes().len(), 2);
+        assert_eq!(
+            diff_with(&from, &to, &options).changes(),
+            &[Change::Changed {
+                path: pointer("/ratio"),
+                from: json!(0.1),
+                to: json!(0.2),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_exact_numbers() {
+        let options = ParseOptions {
+            number_mode: NumberMode::Exact,
+            ..ParseOptions::default()
+        };
+        let from = parse_json_with(
+            r#"{"ratio": 0.1, "id": 12345678901234567890123, "same": 1e2}"#,
+            &options,
+        )
+        .unwrap();
+        let to = parse_json_with(
+            r#"{"ratio": 0.1000000000000000055511151231257827, "id": 12345678901234567890124, "same": 100}"#,
+            &options,
+        )
+        .unwrap();
+
+        let paths: Vec<_> = diff(&from, &to)
+            .changes()
+            .iter()
+            .map(|change| change.path().to_string())
+            .collect();
+        assert_eq!(paths, ["/ratio", "/id"]);
+
+        // integers beyond 2^53 do not round to a nearby float either
+        assert_eq!(
+            diff(&json!(9007199254740993u64), &json!(9007199254740992.0))
+                .changes()
+                .len(),
+            1
+        );
+        assert!(diff(&json!(9007199254740992u64), &json!(9007199254740992.0)).is_empty());
+    }
+
+    #[test]
+    fn test_patches_round_trip() {
+        let strategies = [
+            ArrayStrategy::Index,
+            ArrayStrategy::Lcs,
+            ArrayStrategy::Key("id".to_string()),
+        ];
+        let cases = [
+            (json!([1, 2, 3]), json!([3, 2, 1, 0])),
+            (
+                json!(["a", "b", "c", "d", "e"]),
+                json!(["x", "b", "d", "y", "z", "e"]),
+            ),
+            (
+                json!([{"id": 1}, {"id": 2, "x": [1, 2]}, 7, {"id": 3}]),
+                json!([{"id": 2, "x": [2, 3]}, 8, {"id": 5}, {"id": 1, "y": true}, 7]),
+            ),
+            (
+                json!({"a": [1, {"b": [2, 3]}], "c/d": {"~": 1}}),
+                json!({"a": [{"b": [3]}], "c/d": {"~": 2}}),
+            ),
+            (json!([]), json!({"now": "an object"})),
+            (json!("scalar"), json!(["array"])),
+        ];
+
+        for strategy in strategies {
+            let options = with_arrays(strategy);
+            for (from, to) in &cases {
+                assert_patch_round_trips(from, to, &options);
+                assert_patch_round_trips(to, from, &options);
+            }
+        }
+    }
+}
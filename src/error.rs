@@ -0,0 +1,300 @@
+use std::{cell::Cell, error::Error, fmt, ops::Range};
+
+/// The category of a [`ParseError`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// A character that cannot start any JSON token, e.g. `'` or `#`.
+    UnexpectedCharacter,
+    /// A bare word that is not `true`, `false` or `null`.
+    InvalidLiteral,
+    /// The input ended before the closing `"` of a string.
+    UnterminatedString,
+    /// A backslash followed by a character that is not a JSON escape.
+    InvalidEscape,
+    /// A `\u` escape without four hexadecimal digits.
+    InvalidUnicodeEscape,
+    /// A `\u` escape encoding a UTF-16 surrogate without its partner.
+    LoneSurrogate,
+    /// A raw control character (U+0000 to U+001F) inside a string.
+    ControlCharacter,
+    /// A number that does not follow the JSON number grammar.
+    InvalidNumber,
+    /// A number that is syntactically valid but cannot be represented.
+    NumberOverflow,
+    /// A token that is valid JSON but not allowed at this position.
+    UnexpectedToken,
+    /// The input ended while a value was still incomplete.
+    UnexpectedEof,
+    /// More data follows a complete root value.
+    TrailingData,
+    /// An object key that already appeared in the same object, see `DuplicateKeyPolicy`.
+    DuplicateKey,
+    /// Arrays and objects nested deeper than `ParseOptions::max_depth`.
+    NestingTooDeep,
+}
+
+impl ErrorKind {
+    fn description(&self) -> &'static str {
+        match self {
+            ErrorKind::UnexpectedCharacter => "Unexpected character",
+            ErrorKind::InvalidLiteral => "Invalid literal",
+            ErrorKind::UnterminatedString => "Unterminated string",
+            ErrorKind::InvalidEscape => "Invalid escape sequence",
+            ErrorKind::InvalidUnicodeEscape => "Invalid unicode escape sequence",
+            ErrorKind::LoneSurrogate => "Unpaired surrogate in unicode escape sequence",
+            ErrorKind::ControlCharacter => "Unescaped control character in string",
+            ErrorKind::InvalidNumber => "Invalid number",
+            ErrorKind::NumberOverflow => "Number out of range",
+            ErrorKind::UnexpectedToken => "Unexpected token",
+            ErrorKind::UnexpectedEof => "Unexpected end of input",
+            ErrorKind::TrailingData => "Unexpected trailing data",
+            ErrorKind::DuplicateKey => "Duplicate object key",
+            ErrorKind::NestingTooDeep => "Maximum nesting depth exceeded",
+        }
+    }
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.description())
+    }
+}
+
+/// An error produced while parsing JSON text, with the position where it happened.
+///
+/// Lines and columns are 1-based; columns count characters, not bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    kind: ErrorKind,
+    span: Range<usize>,
+    line: usize,
+    column: usize,
+    expected: Vec<&'static str>,
+    hint: Option<&'static str>,
+    related: Option<Related>,
+}
+
+/// Another place in the input that an error refers to, e.g. the first occurrence of a key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Related {
+    span: Range<usize>,
+    line: usize,
+    column: usize,
+}
+
+impl ParseError {
+    pub(crate) fn new(kind: ErrorKind, span: Range<usize>, lines: &LineIndex<'_>) -> Self {
+        let (line, column) = lines.position(span.start);
+
+        ParseError {
+            kind,
+            span,
+            line,
+            column,
+            expected: Vec::new(),
+            hint: None,
+            related: None,
+        }
+    }
+
+    pub(crate) fn expecting(mut self, expected: &[&'static str]) -> Self {
+        self.expected = expected.to_vec();
+        self
+    }
+
+    pub(crate) fn hinting(mut self, hint: &'static str) -> Self {
+        self.hint = Some(hint);
+        self
+    }
+
+    pub(crate) fn relating(mut self, span: Range<usize>, lines: &LineIndex<'_>) -> Self {
+        let (line, column) = lines.position(span.start);
+        self.related = Some(Related { span, line, column });
+        self
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// Byte offset of the start of the offending input.
+    pub fn offset(&self) -> usize {
+        self.span.start
+    }
+
+    /// Byte range of the offending input.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    /// Descriptions of the tokens that would have been accepted at this position, e.g. `','`.
+    pub fn expected(&self) -> &[&'static str] {
+        &self.expected
+    }
+
+    /// A suggestion on how to fix the input, e.g. "trailing comma not allowed".
+    pub fn hint(&self) -> Option<&'static str> {
+        self.hint
+    }
+
+    /// Byte range of an earlier part of the input involved in the error, e.g. the first
+    /// occurrence of a duplicate key.
+    pub fn related_span(&self) -> Option<Range<usize>> {
+        self.related.as_ref().map(|related| related.span.clone())
+    }
+
+    /// Line and column of [`related_span`](Self::related_span).
+    pub fn related_position(&self) -> Option<(usize, usize)> {
+        self.related
+            .as_ref()
+            .map(|related| (related.line, related.column))
+    }
+}
+
+impl ParseError {
+    /// Writes the kind and the expected tokens, without the position.
+    pub(crate) fn fmt_message(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.kind)?;
+
+        if let Some((last, rest)) = self.expected.split_last() {
+            write!(f, ", expected ")?;
+            if !rest.is_empty() {
+                write!(f, "{} or ", rest.join(", "))?;
+            }
+            write!(f, "{}", last)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.fmt_message(f)?;
+        write!(f, " at line {}, column {}", self.line, self.column)?;
+
+        if let Some((line, column)) = self.related_position() {
+            write!(f, ", first seen at line {}, column {}", line, column)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl Error for ParseError {}
+
+/// Start offsets of the lines of an input, for turning byte offsets into lines and columns.
+///
+/// Built once per input, so reporting many errors does not rescan the text for each of them.
+#[derive(Debug)]
+pub(crate) struct LineIndex<'a> {
+    input: &'a str,
+    starts: Vec<usize>,
+    /// Offset and column of the previous lookup.
+    last: Cell<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(input.match_indices('\n').map(|(i, _)| i + 1))
+            .collect();
+
+        LineIndex {
+            input,
+            starts,
+            last: Cell::new((0, 1)),
+        }
+    }
+
+    fn position(&self, offset: usize) -> (usize, usize) {
+        let offset = offset.min(self.input.len());
+        let line = self.starts.partition_point(|&start| start <= offset);
+        let line_start = self.starts[line - 1];
+
+        // errors mostly come in input order, so keep counting from the previous one on the same line
+        let (from, column) = match self.last.get() {
+            (last, column) if line_start <= last && last <= offset => (last, column),
+            _ => (line_start, 1),
+        };
+        let column = column + self.input[from..offset].chars().count();
+        self.last.set((offset, column));
+
+        (line, column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_error_line_and_column() {
+        let input = "{\n  \"a\": tru\n}";
+        let error = ParseError::new(ErrorKind::InvalidLiteral, 9..12, &LineIndex::new(input));
+
+        assert_eq!(error.line(), 2);
+        assert_eq!(error.column(), 8);
+        assert_eq!(error.offset(), 9);
+    }
+
+    #[test]
+    fn test_parse_error_column_counts_characters() {
+        let input = "[\"é\", x]";
+        let error = ParseError::new(ErrorKind::UnexpectedCharacter, 7..8, &LineIndex::new(input));
+
+        assert_eq!(error.column(), 7);
+    }
+
+    #[test]
+    fn test_line_index_positions_in_any_order() {
+        let input = "[\"é\", x,\n  y,\n\n  \"ü\", z]";
+        let lines = LineIndex::new(input);
+        let offsets = [0, 2, 7, 11, 13, 16, 21, 25, 9, 4, 19, 15, input.len()];
+
+        for offset in offsets {
+            let before = &input[..offset];
+            let line = before.matches('\n').count() + 1;
+            let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+            let column = before[line_start..].chars().count() + 1;
+
+            assert_eq!(lines.position(offset), (line, column), "offset {}", offset);
+        }
+    }
+
+    #[test]
+    fn test_parse_error_display_with_expected_tokens() {
+        let error = ParseError::new(
+            ErrorKind::UnexpectedToken,
+            8..9,
+            &LineIndex::new(r#"{"a": 1 "b": 2}"#),
+        )
+        .expecting(&["','", "'}'"]);
+
+        assert_eq!(
+            error.to_string(),
+            "Unexpected token, expected ',' or '}' at line 1, column 9"
+        );
+    }
+
+    #[test]
+    fn test_parse_error_display_with_related_span() {
+        let input = "{\"a\": 1,\n \"a\": 2}";
+        let lines = LineIndex::new(input);
+        let error = ParseError::new(ErrorKind::DuplicateKey, 10..13, &lines).relating(1..4, &lines);
+
+        assert_eq!(error.related_span(), Some(1..4));
+        assert_eq!(
+            error.to_string(),
+            "Duplicate object key at line 2, column 2, first seen at line 1, column 2"
+        );
+    }
+}
@@ -0,0 +1,774 @@
+use std::{
+    cmp::Ordering,
+    fmt::{self, Write},
+    ops::RangeInclusive,
+};
+
+use crate::decimal::parse_exponent;
+
+/// Mantissas up to this value convert to an `f64` without rounding.
+const MAX_EXACT_MANTISSA: u64 = 1 << 53;
+
+/// The powers of ten that are exactly representable as an `f64`.
+const POWERS_OF_TEN: [f64; 23] = [
+    1e0, 1e1, 1e2, 1e3, 1e4, 1e5, 1e6, 1e7, 1e8, 1e9, 1e10, 1e11, 1e12, 1e13, 1e14, 1e15, 1e16,
+    1e17, 1e18, 1e19, 1e20, 1e21, 1e22,
+];
+
+/// Significant digits kept by the slow path.
+///
+/// A point halfway between two adjacent `f64` values has at most 768 significant digits, so the
+/// digits past this many can only tell whether the value lies above such a point.
+const MAX_DIGITS: usize = 800;
+
+/// Binary exponent of the least significant bit of the smallest subnormal `f64`.
+const MIN_EXPONENT: i64 = -1074;
+
+/// Converts text following the JSON number grammar to the nearest `f64`, rounding ties to even.
+///
+/// Values beyond the `f64` range become infinite and values too small to represent become zero.
+pub(crate) fn parse_float(text: &str) -> f64 {
+    let (negative, magnitude) = match text.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, text),
+    };
+    let value = parse_magnitude(magnitude);
+
+    if negative {
+        -value
+    } else {
+        value
+    }
+}
+
+fn parse_magnitude(text: &str) -> f64 {
+    let (mantissa, exponent) = match text.find(['e', 'E']) {
+        Some(index) => (&text[..index], parse_exponent(&text[index + 1..])),
+        None => (text, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+
+    // the value is the integer formed by all digits, times 10^exponent
+    let exponent = exponent - fraction.len() as i64;
+    let mut digits = integer
+        .bytes()
+        .chain(fraction.bytes())
+        .map(|b| b - b'0')
+        .skip_while(|&d| d == 0);
+
+    let mut significand = 0u64;
+    let mut count = 0i64;
+    for digit in digits.by_ref().take(19) {
+        significand = significand * 10 + digit as u64;
+        count += 1;
+    }
+    let remaining = digits.count() as i64;
+
+    if significand == 0 {
+        return 0.0;
+    }
+
+    // the value lies in [10^(magnitude - 1), 10^magnitude)
+    let magnitude = count + remaining + exponent;
+    if magnitude > 310 {
+        return f64::INFINITY;
+    }
+    if magnitude < -324 {
+        return 0.0;
+    }
+
+    if remaining == 0 {
+        if let Some(value) = fast_path(significand, exponent) {
+            return value;
+        }
+    }
+
+    slow_path(
+        integer.bytes().chain(fraction.bytes()).map(|b| b - b'0'),
+        exponent,
+    )
+}
+
+/// Converts `significand × 10^exponent` with a single correctly rounded float operation, when
+/// both operands are exact.
+fn fast_path(significand: u64, exponent: i64) -> Option<f64> {
+    if significand > MAX_EXACT_MANTISSA {
+        return None;
+    }
+
+    match exponent {
+        0..=22 => Some(significand as f64 * POWERS_OF_TEN[exponent as usize]),
+        -22..=-1 => Some(significand as f64 / POWERS_OF_TEN[-exponent as usize]),
+        // move part of a larger exponent into the significand while it stays exact
+        23..=37 => {
+            let shifted = 10u64
+                .checked_pow((exponent - 22) as u32)
+                .and_then(|scale| significand.checked_mul(scale))
+                .filter(|&shifted| shifted <= MAX_EXACT_MANTISSA)?;
+            Some(shifted as f64 * POWERS_OF_TEN[22])
+        }
+        _ => None,
+    }
+}
+
+/// Converts `digits × 10^exponent` exactly with big integer arithmetic.
+fn slow_path(digits: impl Iterator<Item = u8>, mut exponent: i64) -> f64 {
+    let mut significant: Vec<u8> = digits.skip_while(|&d| d == 0).collect();
+    while significant.last() == Some(&0) {
+        significant.pop();
+        exponent += 1;
+    }
+    if significant.len() > MAX_DIGITS {
+        let beyond = significant.len() - MAX_DIGITS;
+        let sticky = significant[MAX_DIGITS..].iter().any(|&d| d != 0);
+        significant.truncate(MAX_DIGITS);
+        exponent += beyond as i64;
+        // a trailing 1 keeps the value strictly above any halfway point it passed
+        if sticky {
+            significant.push(1);
+            exponent -= 1;
+        }
+    }
+
+    // the value is exactly numerator / denominator
+    let mut numerator = Big::from_digits(&significant);
+    let mut denominator = Big::from_u32(1);
+    if exponent >= 0 {
+        numerator.mul_pow10(exponent as u32);
+    } else {
+        denominator.mul_pow10(-exponent as u32);
+    }
+
+    // the binary exponent of the leading bit, so that 2^binary <= value < 2^(binary + 1)
+    let mut binary = numerator.bit_len() as i64 - denominator.bit_len() as i64;
+    if !at_least_scaled(&numerator, &denominator, binary) {
+        binary -= 1;
+    }
+    if binary > 1023 {
+        return f64::INFINITY;
+    }
+
+    // scale so that the quotient holds the 53 bits of the result, fewer for subnormals
+    let mut lsb = (binary - 52).max(MIN_EXPONENT);
+    if lsb >= 0 {
+        denominator.shl(lsb as u32);
+    } else {
+        numerator.shl(-lsb as u32);
+    }
+    let mut mantissa = numerator.div_rem(&denominator);
+
+    // the remainder left in the numerator decides the rounding, ties go to the even mantissa
+    numerator.shl(1);
+    match numerator.cmp(&denominator) {
+        Ordering::Greater => mantissa += 1,
+        Ordering::Equal if mantissa & 1 == 1 => mantissa += 1,
+        _ => {}
+    }
+    if mantissa == MAX_EXACT_MANTISSA {
+        mantissa >>= 1;
+        lsb += 1;
+    }
+
+    if mantissa < 1 << 52 {
+        // subnormal, its bits are the mantissa itself
+        return f64::from_bits(mantissa);
+    }
+    let biased = lsb + 1075;
+    if biased >= 2047 {
+        return f64::INFINITY;
+    }
+    f64::from_bits(((biased as u64) << 52) | (mantissa & ((1 << 52) - 1)))
+}
+
+/// Whether `numerator / denominator >= 2^exponent`.
+fn at_least_scaled(numerator: &Big, denominator: &Big, exponent: i64) -> bool {
+    if exponent >= 0 {
+        let mut scaled = denominator.clone();
+        scaled.shl(exponent as u32);
+        *numerator >= scaled
+    } else {
+        let mut scaled = numerator.clone();
+        scaled.shl(-exponent as u32);
+        scaled >= *denominator
+    }
+}
+
+/// An arbitrary precision unsigned integer, as little endian 32-bit limbs without trailing zeros.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Big {
+    limbs: Vec<u32>,
+}
+
+impl Big {
+    fn from_u32(value: u32) -> Self {
+        let mut big = Big { limbs: vec![value] };
+        big.trim();
+        big
+    }
+
+    fn from_digits(digits: &[u8]) -> Self {
+        let mut big = Big { limbs: Vec::new() };
+        for chunk in digits.chunks(9) {
+            let value = chunk.iter().fold(0u32, |acc, &d| acc * 10 + d as u32);
+            big.mul_small(10u32.pow(chunk.len() as u32));
+            big.add_small(value);
+        }
+        big
+    }
+
+    fn trim(&mut self) {
+        while self.limbs.last() == Some(&0) {
+            self.limbs.pop();
+        }
+    }
+
+    fn bit_len(&self) -> u64 {
+        match self.limbs.last() {
+            Some(&top) => self.limbs.len() as u64 * 32 - top.leading_zeros() as u64,
+            None => 0,
+        }
+    }
+
+    fn mul_small(&mut self, factor: u32) {
+        let mut carry = 0u64;
+        for limb in self.limbs.iter_mut() {
+            let product = *limb as u64 * factor as u64 + carry;
+            *limb = product as u32;
+            carry = product >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+        self.trim();
+    }
+
+    fn add_small(&mut self, value: u32) {
+        let mut carry = value as u64;
+        for limb in self.limbs.iter_mut() {
+            if carry == 0 {
+                return;
+            }
+            let sum = *limb as u64 + carry;
+            *limb = sum as u32;
+            carry = sum >> 32;
+        }
+        if carry > 0 {
+            self.limbs.push(carry as u32);
+        }
+    }
+
+    fn mul_pow10(&mut self, mut exponent: u32) {
+        while exponent >= 9 {
+            self.mul_small(1_000_000_000);
+            exponent -= 9;
+        }
+        self.mul_small(10u32.pow(exponent));
+    }
+
+    fn shl(&mut self, bits: u32) {
+        if self.limbs.is_empty() {
+            return;
+        }
+        let (limbs, bits) = ((bits / 32) as usize, bits % 32);
+        if bits > 0 {
+            let mut carry = 0u32;
+            for limb in self.limbs.iter_mut() {
+                let shifted = (*limb << bits) | carry;
+                carry = *limb >> (32 - bits);
+                *limb = shifted;
+            }
+            if carry > 0 {
+                self.limbs.push(carry);
+            }
+        }
+        self.limbs.splice(0..0, std::iter::repeat_n(0, limbs));
+    }
+
+    /// Subtracts `other`, which must not be larger than `self`.
+    fn sub_assign(&mut self, other: &Big) {
+        let mut borrow = 0i64;
+        for (index, limb) in self.limbs.iter_mut().enumerate() {
+            let difference =
+                *limb as i64 - other.limbs.get(index).copied().unwrap_or(0) as i64 - borrow;
+            *limb = difference.rem_euclid(1 << 32) as u32;
+            borrow = (difference < 0) as i64;
+        }
+        self.trim();
+    }
+
+    /// Divides by `divisor`, keeping the remainder in `self` and returning the quotient.
+    ///
+    /// The quotient must fit in 64 bits.
+    fn div_rem(&mut self, divisor: &Big) -> u64 {
+        let mut quotient = 0u64;
+        if *self < *divisor {
+            return quotient;
+        }
+
+        let shift = self.bit_len() - divisor.bit_len();
+        let mut shifted = divisor.clone();
+        shifted.shl(shift as u32);
+        for bit in (0..=shift).rev() {
+            if *self >= shifted {
+                self.sub_assign(&shifted);
+                quotient |= 1 << bit;
+            }
+            shifted.shr1();
+        }
+        quotient
+    }
+
+    fn shr1(&mut self) {
+        let mut carry = 0u32;
+        for limb in self.limbs.iter_mut().rev() {
+            let shifted = (*limb >> 1) | (carry << 31);
+            carry = *limb & 1;
+            *limb = shifted;
+        }
+        self.trim();
+    }
+}
+
+impl PartialOrd for Big {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Big {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.limbs
+            .len()
+            .cmp(&other.limbs.len())
+            .then_with(|| self.limbs.iter().rev().cmp(other.limbs.iter().rev()))
+    }
+}
+
+/// The shortest decimal digits that convert back to the same `f64`, with the value being
+/// `0.digits × 10^exponent`.
+pub(crate) struct ShortestDigits {
+    buffer: [u8; 17],
+    len: usize,
+    exponent: i32,
+}
+
+impl ShortestDigits {
+    /// Computes the digits of a finite `value`, ignoring its sign. Zero has the single digit `0`.
+    ///
+    /// The digits come from the standard library's shortest representation (Grisu with a Dragon4
+    /// fallback), which is exact and does not depend on the platform.
+    pub(crate) fn new(value: f64) -> Self {
+        let mut scientific = StackBuffer::<32>::new();
+        // writing to a fixed buffer cannot fail, the longest f64 takes 24 bytes
+        let _ = write!(scientific, "{:e}", value.abs());
+        let text = scientific.as_str();
+        let (mantissa, exponent) = text.split_once('e').unwrap_or((text, "0"));
+
+        let mut digits = ShortestDigits {
+            buffer: [0; 17],
+            len: 0,
+            exponent: exponent.parse::<i32>().unwrap_or(0) + 1,
+        };
+        for digit in mantissa.bytes().filter(u8::is_ascii_digit) {
+            digits.buffer[digits.len] = digit;
+            digits.len += 1;
+        }
+        digits
+    }
+
+    /// The digits as ASCII, without leading or trailing zeros except for zero itself.
+    pub(crate) fn digits(&self) -> &str {
+        // the buffer only ever holds ASCII digits
+        std::str::from_utf8(&self.buffer[..self.len]).unwrap_or("0")
+    }
+
+    /// The position of the decimal point relative to the first digit.
+    pub(crate) fn exponent(&self) -> i32 {
+        self.exponent
+    }
+}
+
+/// How a finite float is laid out once its shortest digits are known.
+pub(crate) struct FloatLayout {
+    /// The decimal exponents, as in [`ShortestDigits::exponent`], written in plain notation.
+    pub(crate) plain: RangeInclusive<i32>,
+    /// Appended to integral values in plain notation, e.g. `.0`.
+    pub(crate) integral_suffix: &'static str,
+    /// Writes `e+21` rather than `e21`.
+    pub(crate) exponent_plus: bool,
+    /// Pads the exponent with zeros to this many digits, e.g. `e-05`.
+    pub(crate) exponent_digits: usize,
+}
+
+impl FloatLayout {
+    /// The shortest JSON that reads back as a float, see [`write_float`].
+    pub(crate) const JSON: FloatLayout = FloatLayout {
+        plain: -5..=21,
+        integral_suffix: ".0",
+        exponent_plus: false,
+        exponent_digits: 1,
+    };
+    /// Python's `repr`, as used by `json.dumps`.
+    pub(crate) const PYTHON: FloatLayout = FloatLayout {
+        plain: -3..=16,
+        integral_suffix: ".0",
+        exponent_plus: true,
+        exponent_digits: 2,
+    };
+    /// JavaScript's `Number.prototype.toString`, which Go's `encoding/json` also follows.
+    pub(crate) const JAVASCRIPT: FloatLayout = FloatLayout {
+        plain: -5..=21,
+        integral_suffix: "",
+        exponent_plus: true,
+        exponent_digits: 1,
+    };
+}
+
+/// Writes `value` as the shortest JSON number that parses back to the same `f64`.
+///
+/// Values from 1e-6 up to 1e21 are written in plain notation, with a `.0` suffix for integral
+/// values so they read back as floats. Other values use exponent notation such as `1e21` or
+/// `1.5e-7`. Infinities and NaN have no JSON representation and are written as `null`.
+pub(crate) fn write_float<W: fmt::Write + ?Sized>(out: &mut W, value: f64) -> fmt::Result {
+    if !value.is_finite() {
+        return out.write_str("null");
+    }
+    write_float_with(out, value, &FloatLayout::JSON)
+}
+
+/// Writes a finite `value` with its shortest digits, laid out as `layout` says.
+pub(crate) fn write_float_with<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    value: f64,
+    layout: &FloatLayout,
+) -> fmt::Result {
+    if value.is_sign_negative() {
+        out.write_char('-')?;
+    }
+
+    let shortest = ShortestDigits::new(value);
+    let (digits, exponent) = (shortest.digits(), shortest.exponent());
+    let len = digits.len() as i32;
+
+    match exponent {
+        _ if !layout.plain.contains(&exponent) => {
+            let (first, rest) = digits.split_at(1);
+            out.write_str(first)?;
+            if !rest.is_empty() {
+                write!(out, ".{}", rest)?;
+            }
+            let exponent = exponent - 1;
+            let sign = match exponent < 0 {
+                true => "-",
+                false if layout.exponent_plus => "+",
+                false => "",
+            };
+            write!(
+                out,
+                "e{}{:0width$}",
+                sign,
+                exponent.unsigned_abs(),
+                width = layout.exponent_digits
+            )
+        }
+        // an integer, possibly with zeros past the digits
+        _ if len <= exponent => {
+            out.write_str(digits)?;
+            write_zeros(out, exponent - len)?;
+            out.write_str(layout.integral_suffix)
+        }
+        1.. => {
+            let (integer, fraction) = digits.split_at(exponent as usize);
+            write!(out, "{}.{}", integer, fraction)
+        }
+        _ => {
+            out.write_str("0.")?;
+            write_zeros(out, -exponent)?;
+            out.write_str(digits)
+        }
+    }
+}
+
+fn write_zeros<W: fmt::Write + ?Sized>(out: &mut W, count: i32) -> fmt::Result {
+    for _ in 0..count {
+        out.write_char('0')?;
+    }
+    Ok(())
+}
+
+/// A fixed-size `fmt::Write` target, to format small values without allocating.
+struct StackBuffer<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> StackBuffer<N> {
+    fn new() -> Self {
+        StackBuffer {
+            bytes: [0; N],
+            len: 0,
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        std::str::from_utf8(&self.bytes[..self.len]).unwrap_or_default()
+    }
+}
+
+impl<const N: usize> fmt::Write for StackBuffer<N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let end = self.len + s.len();
+        if end > N {
+            return Err(fmt::Error);
+        }
+        self.bytes[self.len..end].copy_from_slice(s.as_bytes());
+        self.len = end;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A small deterministic xorshift generator, so failures can be reproduced.
+    struct Rng(u64);
+
+    impl Rng {
+        fn next(&mut self) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0
+        }
+
+        fn below(&mut self, bound: u64) -> u64 {
+            self.next() % bound
+        }
+    }
+
+    fn assert_parses_like_std(text: &str) {
+        let expected: f64 = text.parse().unwrap();
+        let actual = parse_float(text);
+
+        assert_eq!(
+            actual.to_bits(),
+            expected.to_bits(),
+            "{}: got {:e}, expected {:e}",
+            text,
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_parse_float_fast_path() {
+        assert_eq!(parse_float("0"), 0.0);
+        assert_eq!(parse_float("-0.0").to_bits(), (-0.0f64).to_bits());
+        assert_eq!(parse_float("52.1985"), 52.1985);
+        assert_eq!(parse_float("-1.5E3"), -1500.0);
+        assert_eq!(parse_float("123e30"), 123e30);
+        assert_eq!(parse_float("0.000001"), 1e-6);
+    }
+
+    #[test]
+    fn test_parse_float_tricky_values() {
+        for text in [
+            // around the largest finite value
+            "1.7976931348623157e308",
+            "1.7976931348623158e308",
+            "1.797693134862315807e308",
+            // around the smallest normal value
+            "2.2250738585072011e-308",
+            "2.2250738585072012e-308",
+            "2.2250738585072014e-308",
+            // subnormals and the rounding boundary to zero
+            "4.9406564584124654e-324",
+            "2.4703282292062327e-324",
+            "2.4703282292062328e-324",
+            "7.4109846876186982e-324",
+            "1e-320",
+            // exactly halfway between 1 and the next float, then just above it
+            "1.00000000000000011102230246251565404236316680908203125",
+            "1.00000000000000011102230246251565404236316680908203125000000000000000001",
+            "1.00000000000000011102230246251565404236316680908203124999999999999999999",
+            // halfway between 2^53 and 2^53 + 2
+            "9007199254740993",
+            "9007199254740995",
+            "9007199254740993.0000000000000000000000000001",
+            // classic hard cases
+            "0.30000000000000004",
+            "8.98846567431158e307",
+            "3.0517578125e-05",
+            "7.038531e-26",
+            "9214843084008499",
+            "0.500000000000000166533453693773481063544750213623046875",
+            "3.518437208883201171875e13",
+            "62.5364939768271845828",
+            "8.10109172351e-10",
+            "1.448997445238699",
+            "1e23",
+            "1.0e+308",
+            "123456789012345678901234567890",
+            "0.000000000000000000000000000000000000001234567890123456789",
+        ] {
+            assert_parses_like_std(text);
+        }
+    }
+
+    #[test]
+    fn test_parse_float_out_of_range() {
+        assert_eq!(parse_float("1.7976931348623159e308"), f64::INFINITY);
+        assert_eq!(parse_float("-1e400"), f64::NEG_INFINITY);
+        assert_eq!(parse_float("1e-400"), 0.0);
+        assert_eq!(parse_float("1e99999999999999999999"), f64::INFINITY);
+        assert_eq!(parse_float("1e-99999999999999999999"), 0.0);
+    }
+
+    #[test]
+    fn test_parse_float_long_inputs() {
+        let mut text = String::from("0.");
+        text.push_str(&"0".repeat(5000));
+        text.push_str("12345");
+        assert_eq!(parse_float(&text), 0.0);
+
+        let mut text = String::from("1");
+        text.push_str(&"0".repeat(300));
+        text.push('.');
+        text.push_str(&"9".repeat(2000));
+        assert_parses_like_std(&text);
+
+        let mut text = String::from(
+            "2.4703282292062327208828439643411068618252990130716238221279284125033775",
+        );
+        text.push_str(&"0".repeat(1000));
+        text.push_str("1e-324");
+        assert_parses_like_std(&text);
+    }
+
+    #[test]
+    fn test_parse_float_round_trips_random_floats() {
+        let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+
+        for _ in 0..20_000 {
+            let value = f64::from_bits(rng.next());
+            if !value.is_finite() {
+                continue;
+            }
+            for text in [
+                format!("{:e}", value),
+                format!("{:.3e}", value),
+                format!("{:.25e}", value),
+            ] {
+                assert_parses_like_std(&text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_float_random_decimal_strings() {
+        let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+
+        for _ in 0..20_000 {
+            let length = 1 + rng.below(40) as usize;
+            let digits: String = (0..length)
+                .map(|_| (b'0' + rng.below(10) as u8) as char)
+                .collect();
+            let point = rng.below(length as u64) as usize + 1;
+            let exponent = rng.below(700) as i64 - 350;
+            let text = format!("{}.{}e{}", &digits[..point], &digits[point..], exponent);
+            let text = text.replace(".e", "e");
+
+            assert_parses_like_std(&text);
+        }
+    }
+
+    fn format(value: f64) -> String {
+        let mut out = String::new();
+        write_float(&mut out, value).unwrap();
+        out
+    }
+
+    #[test]
+    fn test_write_float_layout() {
+        for (value, expected) in [
+            (0.1, "0.1"),
+            (0.30000000000000004, "0.30000000000000004"),
+            (1.0, "1.0"),
+            (-2.5, "-2.5"),
+            (100.0, "100.0"),
+            (123456.789, "123456.789"),
+            (1e20, "100000000000000000000.0"),
+            (1e21, "1e21"),
+            (1.5e300, "1.5e300"),
+            (1e-6, "0.000001"),
+            (1.5e-7, "1.5e-7"),
+            (0.0, "0.0"),
+            (-0.0, "-0.0"),
+            (5e-324, "5e-324"),
+            (f64::MAX, "1.7976931348623157e308"),
+            (f64::MIN_POSITIVE, "2.2250738585072014e-308"),
+            (9007199254740993.0, "9007199254740992.0"),
+        ] {
+            assert_eq!(format(value), expected);
+        }
+    }
+
+    #[test]
+    fn test_write_float_other_layouts() {
+        let format_with = |value, layout| {
+            let mut out = String::new();
+            write_float_with(&mut out, value, layout).unwrap();
+            out
+        };
+
+        for (value, python, javascript) in [
+            (1.0, "1.0", "1"),
+            (-0.0, "-0.0", "-0"),
+            (0.0001, "0.0001", "0.0001"),
+            (0.00001, "1e-05", "0.00001"),
+            (1.5e-7, "1.5e-07", "1.5e-7"),
+            (1234567890123456.0, "1234567890123456.0", "1234567890123456"),
+            (1e16, "1e+16", "10000000000000000"),
+            (1e21, "1e+21", "1e+21"),
+            (1.5e300, "1.5e+300", "1.5e+300"),
+            (5e-324, "5e-324", "5e-324"),
+        ] {
+            assert_eq!(format_with(value, &FloatLayout::PYTHON), python);
+            assert_eq!(format_with(value, &FloatLayout::JAVASCRIPT), javascript);
+        }
+    }
+
+    #[test]
+    fn test_write_float_non_finite_as_null() {
+        assert_eq!(format(f64::NAN), "null");
+        assert_eq!(format(f64::INFINITY), "null");
+        assert_eq!(format(f64::NEG_INFINITY), "null");
+    }
+
+    #[test]
+    fn test_write_float_round_trips_random_floats() {
+        let mut rng = Rng(0x6a09_e667_f3bc_c908);
+
+        for _ in 0..20_000 {
+            let value = f64::from_bits(rng.next());
+            if !value.is_finite() {
+                continue;
+            }
+            let text = format(value);
+            let shortest = format!("{:e}", value);
+
+            assert_eq!(parse_float(&text).to_bits(), value.to_bits(), "{}", text);
+            assert_eq!(
+                ShortestDigits::new(value).digits().len(),
+                shortest
+                    .bytes()
+                    .take_while(|&b| b != b'e')
+                    .filter(u8::is_ascii_digit)
+                    .count(),
+                "{}",
+                text
+            );
+        }
+    }
+}
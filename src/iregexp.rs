@@ -0,0 +1,576 @@
+//! I-Regexp (RFC 9485), the interoperable regular expressions used by the JSONPath `match()` and
+//! `search()` functions.
+//!
+//! A pattern is compiled to a small program and run as a Pike VM, which follows every possible
+//! path through the pattern at once. Matching therefore takes time linear in the length of the
+//! text, whatever the pattern.
+
+use crate::unicode::{general_category, GeneralCategory};
+
+/// Upper bound on the number of instructions, which `{n,m}` repetitions can otherwise blow up.
+const MAX_PROGRAM_LEN: usize = 10_000;
+
+/// Upper bound on the nesting of groups, patterns can come from the queried document.
+const MAX_DEPTH: usize = 100;
+
+/// A compiled I-Regexp pattern.
+#[derive(Debug, Clone)]
+pub(crate) struct Regex {
+    program: Vec<Inst>,
+}
+
+#[derive(Debug, Clone)]
+enum Inst {
+    /// Consume one character of the class.
+    Char(CharClass),
+    /// Continue at both targets.
+    Split(usize, usize),
+    Jump(usize),
+    Match,
+}
+
+#[derive(Debug, Clone)]
+enum CharClass {
+    /// `.`, which matches anything but a line break.
+    Any,
+    Set {
+        negated: bool,
+        items: Vec<ClassItem>,
+    },
+}
+
+#[derive(Debug, Clone)]
+enum ClassItem {
+    Range(char, char),
+    /// `\p{..}`, or `\P{..}` when negated.
+    Property {
+        property: Property,
+        negated: bool,
+    },
+}
+
+/// A character property: a major class such as `L` (all letters) or a category such as `Lu`.
+#[derive(Debug, Clone, Copy)]
+enum Property {
+    Class(char),
+    Category(GeneralCategory),
+}
+
+/// A parsed pattern, before compilation.
+enum Node {
+    Class(CharClass),
+    Concat(Vec<Node>),
+    Alternation(Vec<Node>),
+    Repeat {
+        node: Box<Node>,
+        min: u32,
+        max: Option<u32>,
+    },
+}
+
+impl Regex {
+    /// Compiles `pattern`, or returns `None` if it is not a valid I-Regexp.
+    pub(crate) fn new(pattern: &str) -> Option<Regex> {
+        let mut parser = PatternParser {
+            chars: pattern.chars().collect(),
+            position: 0,
+            depth: 0,
+        };
+        let node = parser.alternation()?;
+        if parser.position != parser.chars.len() {
+            return None;
+        }
+
+        let mut compiler = Compiler {
+            program: Vec::new(),
+        };
+        compiler.emit(&node)?;
+        compiler.push(Inst::Match)?;
+
+        Some(Regex {
+            program: compiler.program,
+        })
+    }
+
+    /// Whether the whole of `text` matches the pattern.
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        self.run(text, true)
+    }
+
+    /// Whether some substring of `text` matches the pattern.
+    pub(crate) fn is_found_in(&self, text: &str) -> bool {
+        self.run(text, false)
+    }
+
+    fn run(&self, text: &str, anchored: bool) -> bool {
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        self.add_thread(&mut current, 0);
+
+        for c in text.chars() {
+            if current.matched && !anchored {
+                return true;
+            }
+            if current.pcs.is_empty() && anchored {
+                return false;
+            }
+
+            for &pc in &current.pcs {
+                if let Inst::Char(class) = &self.program[pc] {
+                    if class.matches(c) {
+                        self.add_thread(&mut next, pc + 1);
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+
+            if !anchored {
+                // a match may also start at the next character
+                self.add_thread(&mut current, 0);
+            }
+        }
+
+        current.matched
+    }
+
+    /// Adds the thread at `pc`, following jumps and splits up to the next character or match.
+    fn add_thread(&self, threads: &mut Threads, pc: usize) {
+        let mut pending = vec![pc];
+
+        while let Some(pc) = pending.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            threads.marked.push(pc);
+
+            match self.program[pc] {
+                Inst::Char(_) => threads.pcs.push(pc),
+                Inst::Split(first, second) => {
+                    pending.push(second);
+                    pending.push(first);
+                }
+                Inst::Jump(target) => pending.push(target),
+                Inst::Match => threads.matched = true,
+            }
+        }
+    }
+}
+
+/// The set of program positions alive at one point of the text.
+struct Threads {
+    pcs: Vec<usize>,
+    seen: Vec<bool>,
+    marked: Vec<usize>,
+    matched: bool,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads {
+            pcs: Vec::new(),
+            seen: vec![false; len],
+            marked: Vec::new(),
+            matched: false,
+        }
+    }
+
+    fn clear(&mut self) {
+        for pc in self.marked.drain(..) {
+            self.seen[pc] = false;
+        }
+        self.pcs.clear();
+        self.matched = false;
+    }
+}
+
+impl CharClass {
+    fn literal(c: char) -> Self {
+        CharClass::Set {
+            negated: false,
+            items: vec![ClassItem::Range(c, c)],
+        }
+    }
+
+    fn matches(&self, c: char) -> bool {
+        match self {
+            CharClass::Any => c != '\n' && c != '\r',
+            CharClass::Set { negated, items } => {
+                items.iter().any(|item| item.matches(c)) != *negated
+            }
+        }
+    }
+}
+
+impl ClassItem {
+    fn matches(&self, c: char) -> bool {
+        match *self {
+            ClassItem::Range(start, end) => (start..=end).contains(&c),
+            ClassItem::Property { property, negated } => {
+                let category = general_category(c);
+                let found = match property {
+                    Property::Class(class) => category.major_class() == class,
+                    Property::Category(expected) => category == expected,
+                };
+                found != negated
+            }
+        }
+    }
+}
+
+struct PatternParser {
+    chars: Vec<char>,
+    position: usize,
+    /// Number of groups open at the current position.
+    depth: usize,
+}
+
+impl PatternParser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += 1;
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn alternation(&mut self) -> Option<Node> {
+        let mut branches = vec![self.branch()?];
+        while self.eat('|') {
+            branches.push(self.branch()?);
+        }
+
+        Some(match branches.len() {
+            1 => branches.pop()?,
+            _ => Node::Alternation(branches),
+        })
+    }
+
+    fn branch(&mut self) -> Option<Node> {
+        let mut pieces = Vec::new();
+
+        while !matches!(self.peek(), None | Some('|') | Some(')')) {
+            let atom = self.atom()?;
+            pieces.push(self.quantified(atom)?);
+        }
+
+        Some(Node::Concat(pieces))
+    }
+
+    fn atom(&mut self) -> Option<Node> {
+        let class = match self.next()? {
+            '(' => {
+                if self.depth == MAX_DEPTH {
+                    return None;
+                }
+                self.depth += 1;
+                let node = self.alternation()?;
+                self.depth -= 1;
+                return self.eat(')').then_some(node);
+            }
+            '.' => CharClass::Any,
+            '[' => self.class_expression()?,
+            '\\' => match self.peek()? {
+                'p' | 'P' => CharClass::Set {
+                    negated: false,
+                    items: vec![self.property_escape()?],
+                },
+                _ => CharClass::literal(self.single_char_escape()?),
+            },
+            '*' | '+' | '?' | '{' | '}' | ']' => return None,
+            c => CharClass::literal(c),
+        };
+
+        Some(Node::Class(class))
+    }
+
+    fn quantified(&mut self, atom: Node) -> Option<Node> {
+        let (min, max) = match self.peek() {
+            Some('*') => (0, None),
+            Some('+') => (1, None),
+            Some('?') => (0, Some(1)),
+            Some('{') => {
+                self.position += 1;
+                let min = self.quantity()?;
+                let max = match self.eat(',') {
+                    true if self.peek() == Some('}') => None,
+                    true => Some(self.quantity()?),
+                    false => Some(min),
+                };
+                if !self.eat('}') || max.is_some_and(|max| max < min) {
+                    return None;
+                }
+                return Some(Node::Repeat {
+                    node: Box::new(atom),
+                    min,
+                    max,
+                });
+            }
+            _ => return Some(atom),
+        };
+        self.position += 1;
+
+        Some(Node::Repeat {
+            node: Box::new(atom),
+            min,
+            max,
+        })
+    }
+
+    fn quantity(&mut self) -> Option<u32> {
+        let start = self.position;
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.position += 1;
+        }
+
+        self.chars[start..self.position]
+            .iter()
+            .collect::<String>()
+            .parse()
+            .ok()
+    }
+
+    /// Parses what follows `\` outside of `\p{..}`: an escaped metacharacter or `n`, `r`, `t`.
+    fn single_char_escape(&mut self) -> Option<char> {
+        match self.next()? {
+            'n' => Some('\n'),
+            'r' => Some('\r'),
+            't' => Some('\t'),
+            c @ ('(' | ')' | '*' | '+' | '-' | '.' | '?' | '[' | '\\' | ']' | '^' | '{' | '|'
+            | '}') => Some(c),
+            _ => None,
+        }
+    }
+
+    /// Parses `p{..}` or `P{..}` after a `\`.
+    fn property_escape(&mut self) -> Option<ClassItem> {
+        let negated = self.next()? == 'P';
+        if !self.eat('{') {
+            return None;
+        }
+
+        let start = self.position;
+        while self.peek().is_some_and(|c| c != '}') {
+            self.position += 1;
+        }
+        let name: String = self.chars[start..self.position].iter().collect();
+        if !self.eat('}') {
+            return None;
+        }
+
+        let property = match name.as_str() {
+            "L" | "M" | "N" | "P" | "S" | "Z" | "C" => Property::Class(name.chars().next()?),
+            // surrogates are not characters, so `Cs` is left out of I-Regexp
+            _ => Property::Category(GeneralCategory::from_abbreviation(&name)?),
+        };
+
+        Some(ClassItem::Property { property, negated })
+    }
+
+    /// Parses a bracketed class after its `[`, e.g. `[^a-z_\p{Nd}]`.
+    fn class_expression(&mut self) -> Option<CharClass> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+
+        // a `-` may appear as itself first and last
+        if self.eat('-') {
+            items.push(ClassItem::Range('-', '-'));
+        } else {
+            items.push(self.class_item()?);
+        }
+        loop {
+            match self.peek()? {
+                ']' => break,
+                '-' => {
+                    self.position += 1;
+                    if self.peek() != Some(']') {
+                        return None;
+                    }
+                    items.push(ClassItem::Range('-', '-'));
+                }
+                _ => items.push(self.class_item()?),
+            }
+        }
+        self.position += 1;
+
+        Some(CharClass::Set { negated, items })
+    }
+
+    fn class_item(&mut self) -> Option<ClassItem> {
+        if self.peek() == Some('\\') && matches!(self.chars.get(self.position + 1), Some('p' | 'P'))
+        {
+            self.position += 1;
+            return self.property_escape();
+        }
+
+        let start = self.class_char()?;
+        let is_range = self.peek() == Some('-')
+            && self.chars.get(self.position + 1).is_some_and(|&c| c != ']');
+        if !is_range {
+            return Some(ClassItem::Range(start, start));
+        }
+
+        self.position += 1;
+        let end = self.class_char()?;
+        (start <= end).then_some(ClassItem::Range(start, end))
+    }
+
+    fn class_char(&mut self) -> Option<char> {
+        match self.next()? {
+            '\\' => self.single_char_escape(),
+            '-' | '[' | ']' => None,
+            c => Some(c),
+        }
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Option<usize> {
+        if self.program.len() == MAX_PROGRAM_LEN {
+            return None;
+        }
+        self.program.push(inst);
+        Some(self.program.len() - 1)
+    }
+
+    fn emit(&mut self, node: &Node) -> Option<()> {
+        match node {
+            Node::Class(class) => {
+                self.push(Inst::Char(class.clone()))?;
+            }
+            Node::Concat(nodes) => {
+                for node in nodes {
+                    self.emit(node)?;
+                }
+            }
+            Node::Alternation(branches) => {
+                let mut jumps = Vec::new();
+                for (i, branch) in branches.iter().enumerate() {
+                    if i + 1 == branches.len() {
+                        self.emit(branch)?;
+                        break;
+                    }
+                    let split = self.push(Inst::Split(0, 0))?;
+                    self.emit(branch)?;
+                    jumps.push(self.push(Inst::Jump(0))?);
+                    self.program[split] = Inst::Split(split + 1, self.program.len());
+                }
+                let end = self.program.len();
+                for jump in jumps {
+                    self.program[jump] = Inst::Jump(end);
+                }
+            }
+            Node::Repeat { node, min, max } => {
+                for _ in 0..*min {
+                    self.emit(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.emit(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.emit(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+            }
+        }
+
+        Some(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_match(pattern: &str, text: &str) -> bool {
+        Regex::new(pattern).unwrap().is_match(text)
+    }
+
+    #[test]
+    fn test_full_match() {
+        assert!(is_match("a.c", "abc"));
+        assert!(!is_match("a.c", "abcd"));
+        assert!(!is_match("a.c", "a\nc"));
+        assert!(is_match("(ab|cd)+x?", "abcdab"));
+        assert!(is_match("[a-c-]{2,3}", "a-c"));
+        assert!(!is_match("[a-c-]{2,3}", "abca"));
+        assert!(is_match("[^0-9]*", "abc"));
+        assert!(is_match("x{3,}", "xxxxx"));
+        assert!(!is_match("x{3,}", "xx"));
+        assert!(is_match("\\.\\*\\n", ".*\n"));
+        assert!(is_match("", ""));
+        assert!(is_match("^$", "^$"));
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let nested = |depth: usize| format!("{}a{}", "(".repeat(depth), ")".repeat(depth));
+
+        assert!(is_match(&nested(MAX_DEPTH), "a"));
+        assert!(Regex::new(&nested(MAX_DEPTH + 1)).is_none());
+        assert!(Regex::new(&"(".repeat(100_000)).is_none());
+    }
+
+    #[test]
+    fn test_search() {
+        let regex = Regex::new("[0-9]+").unwrap();
+
+        assert!(regex.is_found_in("order 42"));
+        assert!(!regex.is_found_in("no digits"));
+        assert!(Regex::new("").unwrap().is_found_in("anything"));
+    }
+
+    #[test]
+    fn test_properties() {
+        assert!(is_match("\\p{Lu}\\p{Ll}+", "Élan"));
+        assert!(is_match("\\p{L}+", "日本語"));
+        assert!(is_match("\\P{N}", "a"));
+        assert!(!is_match("\\P{N}", "٣"));
+        assert!(is_match("[\\p{Nd}_]+", "1_٣"));
+    }
+
+    #[test]
+    fn test_invalid_patterns() {
+        for pattern in [
+            "(", "a)", "*", "a**", "[]", "[a", "a{2,1}", "a{,2}", "\\d{", "\\w", "\\p{Cs}",
+            "\\p{X}", "[a-]b-]", "[z-a]", "]", "}",
+        ] {
+            assert!(Regex::new(pattern).is_none(), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_nested_repetition_is_linear() {
+        let regex = Regex::new("(a*)*b").unwrap();
+
+        assert!(!regex.is_match(&"a".repeat(10_000)));
+        assert!(Regex::new("((a{100}){100}){100}").is_none());
+    }
+}
@@ -0,0 +1,1489 @@
+use std::{borrow::Cow, cmp::Ordering, error::Error, fmt, rc::Rc, str::FromStr};
+
+use crate::{
+    iregexp::Regex, path::PathSegment, pointer::JsonPointer, tokenizer::parse_number,
+    types::JsonValue,
+};
+
+/// Largest index or slice bound, 2^53 - 1, the integer range of I-JSON.
+const MAX_INDEX: i64 = (1 << 53) - 1;
+
+/// Upper bound on the nesting of parentheses, filters and function arguments in a query.
+const MAX_DEPTH: usize = 100;
+
+/// A parsed JSONPath query (RFC 9535), such as `$..orders[?@.total > 100]`.
+#[derive(Debug, Clone)]
+pub struct JsonPath {
+    segments: Vec<Segment>,
+}
+
+/// A node selected by a query: the value and where it is in the queried document.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueryMatch<'v> {
+    pub value: &'v JsonValue,
+    pub path: NormalizedPath,
+}
+
+/// The location of a node, written as a JSONPath normalized path such as `$['orders'][0]`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct NormalizedPath {
+    segments: Vec<PathSegment>,
+}
+
+/// Error returned when a JSONPath query is not valid, with the byte offset where it was found.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonPathError {
+    message: String,
+    offset: usize,
+}
+
+impl JsonPathError {
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Byte offset in the query of the character that could not be parsed.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+}
+
+impl fmt::Display for JsonPathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at offset {}", self.message, self.offset)
+    }
+}
+
+impl Error for JsonPathError {}
+
+#[derive(Debug, Clone)]
+struct Segment {
+    /// `..`, which applies the selectors to the node and all of its descendants.
+    descendant: bool,
+    selectors: Vec<Selector>,
+}
+
+#[derive(Debug, Clone)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice {
+        start: Option<i64>,
+        end: Option<i64>,
+        step: Option<i64>,
+    },
+    Filter(Logical),
+}
+
+/// A filter expression, evaluating to true or false for each candidate node.
+#[derive(Debug, Clone)]
+enum Logical {
+    Or(Vec<Logical>),
+    And(Vec<Logical>),
+    Not(Box<Logical>),
+    Compare(Comparable, CompareOp, Comparable),
+    /// A query on its own, true when it selects at least one node.
+    Exists(Query),
+    /// A function returning a logical value, such as `match()`.
+    Test(FunctionCall),
+}
+
+/// A query inside a filter, starting at the root (`$`) or at the current node (`@`).
+#[derive(Debug, Clone)]
+struct Query {
+    absolute: bool,
+    segments: Vec<Segment>,
+}
+
+/// Something with a single value, or none: a literal, a singular query or a function.
+#[derive(Debug, Clone)]
+enum Comparable {
+    Literal(JsonValue),
+    Query(Query),
+    Function(FunctionCall),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone)]
+struct FunctionCall {
+    function: Function,
+    args: Vec<Argument>,
+    /// The pattern of `match()` or `search()`, compiled once when it is a literal.
+    regex: Option<Regex>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Function {
+    Length,
+    Count,
+    Match,
+    Search,
+    Value,
+}
+
+#[derive(Debug, Clone)]
+enum Argument {
+    Value(Comparable),
+    Nodes(Query),
+}
+
+/// The types of the function extension system of RFC 9535.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Type {
+    Value,
+    Logical,
+    Nodes,
+}
+
+impl Function {
+    fn lookup(name: &str) -> Option<Function> {
+        match name {
+            "length" => Some(Function::Length),
+            "count" => Some(Function::Count),
+            "match" => Some(Function::Match),
+            "search" => Some(Function::Search),
+            "value" => Some(Function::Value),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Function::Length => "length",
+            Function::Count => "count",
+            Function::Match => "match",
+            Function::Search => "search",
+            Function::Value => "value",
+        }
+    }
+
+    fn parameters(self) -> &'static [Type] {
+        match self {
+            Function::Length => &[Type::Value],
+            Function::Count | Function::Value => &[Type::Nodes],
+            Function::Match | Function::Search => &[Type::Value, Type::Value],
+        }
+    }
+
+    fn result(self) -> Type {
+        match self {
+            Function::Length | Function::Count | Function::Value => Type::Value,
+            Function::Match | Function::Search => Type::Logical,
+        }
+    }
+}
+
+impl JsonPath {
+    /// Parses a query, checking its syntax and the types of its function calls.
+    pub fn parse(query: &str) -> Result<JsonPath, JsonPathError> {
+        let mut parser = QueryParser {
+            input: query,
+            position: 0,
+            depth: 0,
+        };
+
+        if !parser.eat('$') {
+            return Err(parser.error("a query must start with '$'"));
+        }
+        let segments = parser.segments()?;
+        if parser.position != query.len() {
+            return Err(parser.error("unexpected character"));
+        }
+
+        Ok(JsonPath { segments })
+    }
+
+    /// Runs the query with `root` as `$`, returning the selected nodes in order.
+    pub fn query<'v>(&self, root: &'v JsonValue) -> Vec<QueryMatch<'v>> {
+        select(&self.segments, root, (root, Trail(None)))
+            .into_iter()
+            .map(|(value, trail)| QueryMatch {
+                value,
+                path: trail.to_path(),
+            })
+            .collect()
+    }
+
+    /// Like [`query`](JsonPath::query), without computing the paths.
+    pub fn query_values<'v>(&self, root: &'v JsonValue) -> Vec<&'v JsonValue> {
+        select(&self.segments, root, (root, ()))
+            .into_iter()
+            .map(|(value, ())| value)
+            .collect()
+    }
+}
+
+impl FromStr for JsonPath {
+    type Err = JsonPathError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPath::parse(s)
+    }
+}
+
+impl JsonValue {
+    /// Runs a JSONPath query such as `$..orders[?@.total > 100]`, with this value as `$`.
+    pub fn query(&self, query: &str) -> Result<Vec<QueryMatch<'_>>, JsonPathError> {
+        Ok(JsonPath::parse(query)?.query(self))
+    }
+}
+
+impl NormalizedPath {
+    pub fn segments(&self) -> &[PathSegment] {
+        &self.segments
+    }
+
+    /// The same location as a JSON Pointer, e.g. `/orders/0`.
+    pub fn to_pointer(&self) -> JsonPointer {
+        let mut pointer = JsonPointer::root();
+        for segment in &self.segments {
+            match segment {
+                PathSegment::Key(key) => pointer.push(key.as_str()),
+                PathSegment::Index(index) => pointer.push(index.to_string()),
+            }
+        }
+        pointer
+    }
+}
+
+impl fmt::Display for NormalizedPath {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("$")?;
+
+        for segment in &self.segments {
+            let key = match segment {
+                PathSegment::Index(index) => {
+                    write!(f, "[{}]", index)?;
+                    continue;
+                }
+                PathSegment::Key(key) => key,
+            };
+
+            f.write_str("['")?;
+            for c in key.chars() {
+                match c {
+                    '\u{8}' => f.write_str("\\b")?,
+                    '\u{c}' => f.write_str("\\f")?,
+                    '\n' => f.write_str("\\n")?,
+                    '\r' => f.write_str("\\r")?,
+                    '\t' => f.write_str("\\t")?,
+                    '\'' => f.write_str("\\'")?,
+                    '\\' => f.write_str("\\\\")?,
+                    c if c < ' ' => write!(f, "\\u{:04x}", c as u32)?,
+                    c => write!(f, "{}", c)?,
+                }
+            }
+            f.write_str("']")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// An operand of a filter expression, before it is known whether it is compared or tested.
+enum Operand {
+    Literal(JsonValue),
+    Query(Query),
+    Function(FunctionCall),
+}
+
+/// The result of parsing part of a filter: a complete logical expression, or a lone operand
+/// with its offset, which is only valid as a function argument or as part of a comparison.
+enum Expr {
+    Logical(Logical),
+    Operand(Operand, usize),
+}
+
+struct QueryParser<'q> {
+    input: &'q str,
+    position: usize,
+    /// Number of logical expressions open at the current position.
+    depth: usize,
+}
+
+impl QueryParser<'_> {
+    fn error(&self, message: impl Into<String>) -> JsonPathError {
+        self.error_at(self.position, message)
+    }
+
+    fn error_at(&self, offset: usize, message: impl Into<String>) -> JsonPathError {
+        JsonPathError {
+            message: message.into(),
+            offset,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.input[self.position..].chars().next()
+    }
+
+    fn peek_second(&self) -> Option<char> {
+        self.input[self.position..].chars().nth(1)
+    }
+
+    fn next(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let found = self.peek() == Some(expected);
+        if found {
+            self.position += expected.len_utf8();
+        }
+        found
+    }
+
+    fn eat_str(&mut self, expected: &str) -> bool {
+        let found = self.input[self.position..].starts_with(expected);
+        if found {
+            self.position += expected.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonPathError> {
+        match self.eat(expected) {
+            true => Ok(()),
+            false => Err(self.error(format!("expected '{}'", expected))),
+        }
+    }
+
+    fn skip_blank(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t' | '\n' | '\r')) {
+            self.position += 1;
+        }
+    }
+
+    /// Parses segments for as long as they follow, leaving any blank after the last one.
+    fn segments(&mut self) -> Result<Vec<Segment>, JsonPathError> {
+        let mut segments = Vec::new();
+
+        loop {
+            let before = self.position;
+            self.skip_blank();
+
+            let segment = match self.peek() {
+                Some('[') => Segment {
+                    descendant: false,
+                    selectors: self.bracketed_selection()?,
+                },
+                Some('.') if self.peek_second() == Some('.') => {
+                    self.position += 2;
+                    let selectors = match self.peek() {
+                        Some('[') => self.bracketed_selection()?,
+                        _ => vec![self.shorthand_selector("..")?],
+                    };
+                    Segment {
+                        descendant: true,
+                        selectors,
+                    }
+                }
+                Some('.') => {
+                    self.position += 1;
+                    Segment {
+                        descendant: false,
+                        selectors: vec![self.shorthand_selector(".")?],
+                    }
+                }
+                _ => {
+                    self.position = before;
+                    return Ok(segments);
+                }
+            };
+            segments.push(segment);
+        }
+    }
+
+    /// Parses the `*` or member name that follows `.` or `..`.
+    fn shorthand_selector(&mut self, after: &str) -> Result<Selector, JsonPathError> {
+        if self.eat('*') {
+            return Ok(Selector::Wildcard);
+        }
+
+        let start = self.position;
+        match self.peek() {
+            Some(c) if c.is_ascii_alphabetic() || c == '_' || !c.is_ascii() => {}
+            _ => return Err(self.error(format!("expected a member name or '*' after '{}'", after))),
+        }
+        while let Some(c) = self.peek() {
+            if !(c.is_ascii_alphanumeric() || c == '_' || !c.is_ascii()) {
+                break;
+            }
+            self.position += c.len_utf8();
+        }
+
+        Ok(Selector::Name(self.input[start..self.position].to_string()))
+    }
+
+    fn bracketed_selection(&mut self) -> Result<Vec<Selector>, JsonPathError> {
+        self.expect('[')?;
+        self.skip_blank();
+
+        let mut selectors = vec![self.selector()?];
+        loop {
+            self.skip_blank();
+            if !self.eat(',') {
+                break;
+            }
+            self.skip_blank();
+            selectors.push(self.selector()?);
+        }
+        self.expect(']')?;
+
+        Ok(selectors)
+    }
+
+    fn selector(&mut self) -> Result<Selector, JsonPathError> {
+        match self.peek() {
+            Some('\'' | '"') => Ok(Selector::Name(self.string_literal()?)),
+            Some('*') => {
+                self.position += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some('?') => {
+                self.position += 1;
+                self.skip_blank();
+                let expr = self.logical_or()?;
+                Ok(Selector::Filter(self.to_logical(expr)?))
+            }
+            Some('-' | '0'..='9' | ':') => self.index_or_slice(),
+            _ => Err(self.error("expected a selector")),
+        }
+    }
+
+    fn index_or_slice(&mut self) -> Result<Selector, JsonPathError> {
+        let start = match self.peek() {
+            Some(':') => None,
+            _ => Some(self.integer()?),
+        };
+
+        let before = self.position;
+        self.skip_blank();
+        if !self.eat(':') {
+            self.position = before;
+            // `start` is set, as a selector starting with ':' is a slice
+            return Ok(Selector::Index(start.unwrap_or_default()));
+        }
+
+        self.skip_blank();
+        let end = self.optional_integer()?;
+        self.skip_blank();
+        let mut step = None;
+        if self.eat(':') {
+            self.skip_blank();
+            step = self.optional_integer()?;
+        }
+
+        Ok(Selector::Slice { start, end, step })
+    }
+
+    fn optional_integer(&mut self) -> Result<Option<i64>, JsonPathError> {
+        match self.peek() {
+            Some('-' | '0'..='9') => Ok(Some(self.integer()?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Parses an index or slice bound: an integer without leading zeros, in the I-JSON range.
+    fn integer(&mut self) -> Result<i64, JsonPathError> {
+        let start = self.position;
+        let negative = self.eat('-');
+        let digits_start = self.position;
+        while matches!(self.peek(), Some('0'..='9')) {
+            self.position += 1;
+        }
+
+        let digits = &self.input[digits_start..self.position];
+        let canonical = match digits {
+            "" => false,
+            "0" => !negative,
+            _ => !digits.starts_with('0'),
+        };
+
+        self.input[start..self.position]
+            .parse::<i64>()
+            .ok()
+            .filter(|i| canonical && (-MAX_INDEX..=MAX_INDEX).contains(i))
+            .ok_or_else(|| {
+                self.error_at(
+                    start,
+                    "expected an integer between -(2^53-1) and 2^53-1, without leading zeros",
+                )
+            })
+    }
+
+    fn string_literal(&mut self) -> Result<String, JsonPathError> {
+        let start = self.position;
+        let quote = self.next().unwrap_or_default();
+        let mut string = String::new();
+
+        loop {
+            let escape_start = self.position;
+            let c = match self.next() {
+                Some(c) => c,
+                None => return Err(self.error_at(start, "unterminated string literal")),
+            };
+
+            match c {
+                c if c == quote => return Ok(string),
+                '\\' => {
+                    let unescaped = match self.next() {
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some(c @ ('/' | '\\')) => c,
+                        Some(c) if c == quote => c,
+                        Some('u') => self.unicode_escape(escape_start)?,
+                        _ => return Err(self.error_at(escape_start, "invalid escape sequence")),
+                    };
+                    string.push(unescaped);
+                }
+                c if c < ' ' => {
+                    return Err(self.error_at(escape_start, "control character in string literal"))
+                }
+                c => string.push(c),
+            }
+        }
+    }
+
+    /// Parses the hexadecimal digits of a `\u` escape, and the low surrogate that must follow a
+    /// high one.
+    fn unicode_escape(&mut self, start: usize) -> Result<char, JsonPathError> {
+        let invalid = |parser: &Self| parser.error_at(start, "invalid unicode escape sequence");
+
+        let high = self.hex4().ok_or_else(|| invalid(self))?;
+        let code = match high {
+            0xD800..=0xDBFF => {
+                let low = match self.eat_str("\\u") {
+                    true => self.hex4().ok_or_else(|| invalid(self))?,
+                    false => return Err(invalid(self)),
+                };
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(invalid(self));
+                }
+                0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)
+            }
+            code => code,
+        };
+
+        char::from_u32(code).ok_or_else(|| invalid(self))
+    }
+
+    fn hex4(&mut self) -> Option<u32> {
+        let digits = self.input.get(self.position..self.position + 4)?;
+        if !digits.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return None;
+        }
+        self.position += 4;
+        u32::from_str_radix(digits, 16).ok()
+    }
+
+    fn logical_or(&mut self) -> Result<Expr, JsonPathError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("expression is nested too deeply"));
+        }
+
+        self.depth += 1;
+        let expr = self.logical_or_terms();
+        self.depth -= 1;
+        expr
+    }
+
+    fn logical_or_terms(&mut self) -> Result<Expr, JsonPathError> {
+        let mut first = Some(self.logical_and()?);
+        let mut terms = Vec::new();
+
+        loop {
+            let before = self.position;
+            self.skip_blank();
+            if !self.eat_str("||") {
+                self.position = before;
+                break;
+            }
+            if let Some(first) = first.take() {
+                terms.push(self.to_logical(first)?);
+            }
+            self.skip_blank();
+            let term = self.logical_and()?;
+            terms.push(self.to_logical(term)?);
+        }
+
+        Ok(match first {
+            Some(expr) => expr,
+            None => Expr::Logical(Logical::Or(terms)),
+        })
+    }
+
+    fn logical_and(&mut self) -> Result<Expr, JsonPathError> {
+        let mut first = Some(self.basic_expr()?);
+        let mut terms = Vec::new();
+
+        loop {
+            let before = self.position;
+            self.skip_blank();
+            if !self.eat_str("&&") {
+                self.position = before;
+                break;
+            }
+            if let Some(first) = first.take() {
+                terms.push(self.to_logical(first)?);
+            }
+            self.skip_blank();
+            let term = self.basic_expr()?;
+            terms.push(self.to_logical(term)?);
+        }
+
+        Ok(match first {
+            Some(expr) => expr,
+            None => Expr::Logical(Logical::And(terms)),
+        })
+    }
+
+    /// Parses a negation, a parenthesized expression, a comparison or a lone operand.
+    fn basic_expr(&mut self) -> Result<Expr, JsonPathError> {
+        let start = self.position;
+
+        if self.eat('!') {
+            self.skip_blank();
+            let negated = match self.peek() {
+                Some('(') => self.parenthesized()?,
+                _ => {
+                    let operand_start = self.position;
+                    let operand = self.operand()?;
+                    self.to_logical(Expr::Operand(operand, operand_start))?
+                }
+            };
+            return Ok(Expr::Logical(Logical::Not(Box::new(negated))));
+        }
+        if self.peek() == Some('(') {
+            return Ok(Expr::Logical(self.parenthesized()?));
+        }
+
+        let left = self.operand()?;
+        let before = self.position;
+        self.skip_blank();
+        let op = match self.compare_op() {
+            Some(op) => op,
+            None => {
+                self.position = before;
+                return Ok(Expr::Operand(left, start));
+            }
+        };
+        self.skip_blank();
+        let right_start = self.position;
+        let right = self.operand()?;
+
+        Ok(Expr::Logical(Logical::Compare(
+            self.comparable(left, start)?,
+            op,
+            self.comparable(right, right_start)?,
+        )))
+    }
+
+    fn parenthesized(&mut self) -> Result<Logical, JsonPathError> {
+        self.expect('(')?;
+        self.skip_blank();
+        let expr = self.logical_or()?;
+        let logical = self.to_logical(expr)?;
+        self.skip_blank();
+        self.expect(')')?;
+
+        Ok(logical)
+    }
+
+    fn compare_op(&mut self) -> Option<CompareOp> {
+        let ops = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+
+        ops.into_iter()
+            .find(|(text, _)| self.eat_str(text))
+            .map(|(_, op)| op)
+    }
+
+    fn operand(&mut self) -> Result<Operand, JsonPathError> {
+        match self.peek() {
+            Some(c @ ('$' | '@')) => {
+                self.position += 1;
+                Ok(Operand::Query(Query {
+                    absolute: c == '$',
+                    segments: self.segments()?,
+                }))
+            }
+            Some('\'' | '"') => Ok(Operand::Literal(JsonValue::String(self.string_literal()?))),
+            Some('-' | '0'..='9') => Ok(Operand::Literal(self.number()?)),
+            Some('a'..='z') => self.name_operand(),
+            _ => Err(self.error("expected a literal, a query or a function call")),
+        }
+    }
+
+    /// Parses `true`, `false`, `null` or a function call.
+    fn name_operand(&mut self) -> Result<Operand, JsonPathError> {
+        let start = self.position;
+        while matches!(self.peek(), Some('a'..='z' | '0'..='9' | '_')) {
+            self.position += 1;
+        }
+        let name = &self.input[start..self.position];
+
+        if self.peek() != Some('(') {
+            return match name {
+                "true" => Ok(Operand::Literal(JsonValue::Boolean(true))),
+                "false" => Ok(Operand::Literal(JsonValue::Boolean(false))),
+                "null" => Ok(Operand::Literal(JsonValue::Null)),
+                _ => Err(self.error_at(start, "expected a literal, a query or a function call")),
+            };
+        }
+
+        let function = Function::lookup(name)
+            .ok_or_else(|| self.error_at(start, format!("unknown function `{}`", name)))?;
+        self.position += 1;
+        self.skip_blank();
+
+        let mut exprs = Vec::new();
+        if !self.eat(')') {
+            loop {
+                let arg_start = self.position;
+                exprs.push((self.logical_or()?, arg_start));
+                self.skip_blank();
+                if !self.eat(',') {
+                    break;
+                }
+                self.skip_blank();
+            }
+            self.expect(')')?;
+        }
+
+        let parameters = function.parameters();
+        if exprs.len() != parameters.len() {
+            return Err(self.error_at(
+                start,
+                format!(
+                    "`{}()` takes {} argument{}",
+                    name,
+                    parameters.len(),
+                    if parameters.len() == 1 { "" } else { "s" }
+                ),
+            ));
+        }
+
+        let mut args = Vec::new();
+        for ((expr, offset), parameter) in exprs.into_iter().zip(parameters) {
+            let arg = match (parameter, expr) {
+                (Type::Value, Expr::Operand(operand, offset)) => {
+                    Argument::Value(self.comparable(operand, offset)?)
+                }
+                (Type::Nodes, Expr::Operand(Operand::Query(query), _)) => Argument::Nodes(query),
+                (Type::Value, _) => {
+                    return Err(self.error_at(
+                        offset,
+                        format!("`{}()` expects a value, not a logical expression", name),
+                    ))
+                }
+                _ => {
+                    return Err(
+                        self.error_at(offset, format!("`{}()` expects a query argument", name))
+                    )
+                }
+            };
+            args.push(arg);
+        }
+
+        let regex = match args.get(1) {
+            Some(Argument::Value(Comparable::Literal(JsonValue::String(pattern)))) => {
+                Regex::new(pattern)
+            }
+            _ => None,
+        };
+
+        Ok(Operand::Function(FunctionCall {
+            function,
+            args,
+            regex,
+        }))
+    }
+
+    /// Parses a number literal, which follows the JSON number grammar except that `-0` is
+    /// allowed.
+    fn number(&mut self) -> Result<JsonValue, JsonPathError> {
+        let start = self.position;
+        let invalid = |parser: &Self| parser.error_at(start, "invalid number");
+        let skip_digits = |parser: &mut Self| {
+            let digits_start = parser.position;
+            while matches!(parser.peek(), Some('0'..='9')) {
+                parser.position += 1;
+            }
+            parser.position > digits_start
+        };
+
+        self.eat('-');
+        if !self.eat('0') && !skip_digits(self) {
+            return Err(invalid(self));
+        }
+        let mut is_float = false;
+        if self.eat('.') {
+            is_float = true;
+            if !skip_digits(self) {
+                return Err(invalid(self));
+            }
+        }
+        if self.eat('e') || self.eat('E') {
+            is_float = true;
+            let _ = self.eat('+') || self.eat('-');
+            if !skip_digits(self) {
+                return Err(invalid(self));
+            }
+        }
+
+        parse_number(&self.input[start..self.position], is_float)
+            .map(JsonValue::Number)
+            .ok_or_else(|| self.error_at(start, "number out of range"))
+    }
+
+    fn to_logical(&self, expr: Expr) -> Result<Logical, JsonPathError> {
+        match expr {
+            Expr::Logical(logical) => Ok(logical),
+            Expr::Operand(Operand::Query(query), _) => Ok(Logical::Exists(query)),
+            Expr::Operand(Operand::Function(call), offset) => match call.function.result() {
+                Type::Logical | Type::Nodes => Ok(Logical::Test(call)),
+                Type::Value => Err(self.error_at(
+                    offset,
+                    format!(
+                        "the result of `{}()` must be compared",
+                        call.function.name()
+                    ),
+                )),
+            },
+            Expr::Operand(Operand::Literal(_), offset) => {
+                Err(self.error_at(offset, "a literal must be compared"))
+            }
+        }
+    }
+
+    fn comparable(&self, operand: Operand, offset: usize) -> Result<Comparable, JsonPathError> {
+        match operand {
+            Operand::Literal(value) => Ok(Comparable::Literal(value)),
+            Operand::Query(query) if query.is_singular() => Ok(Comparable::Query(query)),
+            Operand::Query(_) => Err(self.error_at(
+                offset,
+                "a query that can select more than one node cannot be used as a value",
+            )),
+            Operand::Function(call) if call.function.result() == Type::Value => {
+                Ok(Comparable::Function(call))
+            }
+            Operand::Function(call) => Err(self.error_at(
+                offset,
+                format!("`{}()` does not return a value", call.function.name()),
+            )),
+        }
+    }
+}
+
+impl Query {
+    /// Whether the query selects at most one node: only names and indexes, one per segment.
+    fn is_singular(&self) -> bool {
+        self.segments.iter().all(|segment| {
+            !segment.descendant
+                && matches!(
+                    segment.selectors.as_slice(),
+                    [Selector::Name(_) | Selector::Index(_)]
+                )
+        })
+    }
+
+    fn select<'a>(&self, root: &'a JsonValue, current: &'a JsonValue) -> Vec<&'a JsonValue> {
+        let start = if self.absolute { root } else { current };
+
+        select(&self.segments, root, (start, ()))
+            .into_iter()
+            .map(|(value, ())| value)
+            .collect()
+    }
+}
+
+/// One step from a node to one of its children.
+#[derive(Debug, Clone, Copy)]
+enum Step<'v> {
+    Key(&'v str),
+    Index(usize),
+}
+
+/// Where a selected node is, tracked only when the caller asks for paths.
+trait Location<'v>: Clone {
+    fn join(&self, step: Step<'v>) -> Self;
+}
+
+impl<'v> Location<'v> for () {
+    fn join(&self, _: Step<'v>) -> Self {}
+}
+
+/// The steps from the root to a node, shared with the trails of the node's descendants.
+#[derive(Clone)]
+struct Trail<'v>(Option<Rc<(Trail<'v>, Step<'v>)>>);
+
+impl<'v> Location<'v> for Trail<'v> {
+    fn join(&self, step: Step<'v>) -> Self {
+        Trail(Some(Rc::new((self.clone(), step))))
+    }
+}
+
+impl Trail<'_> {
+    fn to_path(&self) -> NormalizedPath {
+        let mut segments = Vec::new();
+        let mut trail = self;
+        while let Some(link) = &trail.0 {
+            segments.push(match link.1 {
+                Step::Key(key) => PathSegment::Key(key.to_string()),
+                Step::Index(index) => PathSegment::Index(index),
+            });
+            trail = &link.0;
+        }
+        segments.reverse();
+
+        NormalizedPath { segments }
+    }
+}
+
+fn select<'v, L: Location<'v>>(
+    segments: &[Segment],
+    root: &'v JsonValue,
+    start: (&'v JsonValue, L),
+) -> Vec<(&'v JsonValue, L)> {
+    let mut nodes = vec![start];
+
+    for segment in segments {
+        let mut selected = Vec::new();
+        for (value, location) in nodes {
+            segment.select(root, value, location, &mut selected);
+        }
+        nodes = selected;
+    }
+
+    nodes
+}
+
+fn for_each_child<'v>(value: &'v JsonValue, mut f: impl FnMut(Step<'v>, &'v JsonValue)) {
+    match value {
+        JsonValue::Array(arr) => {
+            for (index, element) in arr.iter().enumerate() {
+                f(Step::Index(index), element);
+            }
+        }
+        JsonValue::Object(obj) => {
+            for (key, member) in obj {
+                f(Step::Key(key), member);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl Segment {
+    fn select<'v, L: Location<'v>>(
+        &self,
+        root: &'v JsonValue,
+        value: &'v JsonValue,
+        location: L,
+        selected: &mut Vec<(&'v JsonValue, L)>,
+    ) {
+        for selector in &self.selectors {
+            selector.select(root, value, &location, selected);
+        }
+
+        // descendants are visited in document order, each node before its children
+        if self.descendant {
+            for_each_child(value, |step, child| {
+                self.select(root, child, location.join(step), selected)
+            });
+        }
+    }
+}
+
+impl Selector {
+    fn select<'v, L: Location<'v>>(
+        &self,
+        root: &'v JsonValue,
+        value: &'v JsonValue,
+        location: &L,
+        selected: &mut Vec<(&'v JsonValue, L)>,
+    ) {
+        match self {
+            Selector::Name(name) => {
+                if let Some((key, member)) =
+                    value.as_object().and_then(|obj| obj.get_key_value(name))
+                {
+                    selected.push((member, location.join(Step::Key(key))));
+                }
+            }
+            Selector::Wildcard => {
+                for_each_child(value, |step, child| {
+                    selected.push((child, location.join(step)))
+                });
+            }
+            Selector::Index(index) => {
+                let arr = match value.as_array() {
+                    Some(arr) => arr,
+                    None => return,
+                };
+                let index = match *index < 0 {
+                    true => arr.len() as i64 + index,
+                    false => *index,
+                };
+                if let Some(element) = usize::try_from(index).ok().and_then(|i| arr.get(i)) {
+                    selected.push((element, location.join(Step::Index(index as usize))));
+                }
+            }
+            Selector::Slice { start, end, step } => {
+                if let Some(arr) = value.as_array() {
+                    for index in slice_indices(arr.len(), *start, *end, *step) {
+                        selected.push((&arr[index], location.join(Step::Index(index))));
+                    }
+                }
+            }
+            Selector::Filter(logical) => {
+                for_each_child(value, |step, child| {
+                    if logical.test(root, child) {
+                        selected.push((child, location.join(step)));
+                    }
+                });
+            }
+        }
+    }
+}
+
+/// The positions selected by `[start:end:step]` in an array of `len` elements, in order.
+fn slice_indices(
+    len: usize,
+    start: Option<i64>,
+    end: Option<i64>,
+    step: Option<i64>,
+) -> Vec<usize> {
+    let len = len as i64;
+    let step = step.unwrap_or(1);
+    let normalize = |i: i64| if i >= 0 { i } else { len + i };
+    let mut indices = Vec::new();
+
+    if step > 0 {
+        let lower = normalize(start.unwrap_or(0)).clamp(0, len);
+        let upper = normalize(end.unwrap_or(len)).clamp(0, len);
+        let mut i = lower;
+        while i < upper {
+            indices.push(i as usize);
+            i += step;
+        }
+    } else if step < 0 {
+        let upper = normalize(start.unwrap_or(len - 1)).clamp(-1, len - 1);
+        let lower = normalize(end.unwrap_or(-len - 1)).clamp(-1, len - 1);
+        let mut i = upper;
+        while lower < i {
+            indices.push(i as usize);
+            i += step;
+        }
+    }
+
+    indices
+}
+
+/// What a function returns.
+enum Outcome<'a> {
+    Value(Option<Cow<'a, JsonValue>>),
+    Logical(bool),
+}
+
+impl Logical {
+    fn test(&self, root: &JsonValue, current: &JsonValue) -> bool {
+        match self {
+            Logical::Or(terms) => terms.iter().any(|term| term.test(root, current)),
+            Logical::And(terms) => terms.iter().all(|term| term.test(root, current)),
+            Logical::Not(term) => !term.test(root, current),
+            Logical::Compare(left, op, right) => compare(
+                left.evaluate(root, current).as_deref(),
+                *op,
+                right.evaluate(root, current).as_deref(),
+            ),
+            Logical::Exists(query) => !query.select(root, current).is_empty(),
+            Logical::Test(call) => match call.evaluate(root, current) {
+                Outcome::Logical(result) => result,
+                // rejected by the parser
+                Outcome::Value(_) => false,
+            },
+        }
+    }
+}
+
+impl Comparable {
+    /// The value, or `None` when a query selects nothing or a function has no result.
+    fn evaluate<'a>(
+        &'a self,
+        root: &'a JsonValue,
+        current: &'a JsonValue,
+    ) -> Option<Cow<'a, JsonValue>> {
+        match self {
+            Comparable::Literal(value) => Some(Cow::Borrowed(value)),
+            Comparable::Query(query) => query
+                .select(root, current)
+                .first()
+                .map(|v| Cow::Borrowed(*v)),
+            Comparable::Function(call) => match call.evaluate(root, current) {
+                Outcome::Value(value) => value,
+                Outcome::Logical(_) => None,
+            },
+        }
+    }
+}
+
+impl FunctionCall {
+    fn evaluate<'a>(&'a self, root: &'a JsonValue, current: &'a JsonValue) -> Outcome<'a> {
+        let value = |i: usize| match &self.args[i] {
+            Argument::Value(comparable) => comparable.evaluate(root, current),
+            Argument::Nodes(_) => None,
+        };
+        let nodes = |i: usize| match &self.args[i] {
+            Argument::Nodes(query) => query.select(root, current),
+            Argument::Value(_) => Vec::new(),
+        };
+
+        match self.function {
+            Function::Length => {
+                let len = value(0).and_then(|value| match value.as_ref() {
+                    JsonValue::String(s) => Some(s.chars().count()),
+                    JsonValue::Array(arr) => Some(arr.len()),
+                    JsonValue::Object(obj) => Some(obj.len()),
+                    _ => None,
+                });
+                Outcome::Value(len.map(|len| Cow::Owned(JsonValue::from(len))))
+            }
+            Function::Count => Outcome::Value(Some(Cow::Owned(JsonValue::from(nodes(0).len())))),
+            Function::Match | Function::Search => {
+                let (text, pattern) = (value(0), value(1));
+                let found = match (text.as_deref(), pattern.as_deref()) {
+                    (Some(JsonValue::String(text)), Some(JsonValue::String(pattern))) => {
+                        let regex = match &self.regex {
+                            Some(regex) => Cow::Borrowed(regex),
+                            None => match Regex::new(pattern) {
+                                Some(regex) => Cow::Owned(regex),
+                                // an invalid pattern matches nothing
+                                None => return Outcome::Logical(false),
+                            },
+                        };
+                        match self.function {
+                            Function::Match => regex.is_match(text),
+                            _ => regex.is_found_in(text),
+                        }
+                    }
+                    _ => false,
+                };
+                Outcome::Logical(found)
+            }
+            Function::Value => match nodes(0).as_slice() {
+                [node] => Outcome::Value(Some(Cow::Borrowed(*node))),
+                _ => Outcome::Value(None),
+            },
+        }
+    }
+}
+
+/// Compares two values as RFC 9535 does: an absent value only equals another absent value, and
+/// only numbers and strings are ordered.
+fn compare(left: Option<&JsonValue>, op: CompareOp, right: Option<&JsonValue>) -> bool {
+    match op {
+        CompareOp::Eq => equal(left, right),
+        CompareOp::Ne => !equal(left, right),
+        CompareOp::Lt => less(left, right),
+        CompareOp::Le => less(left, right) || equal(left, right),
+        CompareOp::Gt => less(right, left),
+        CompareOp::Ge => less(right, left) || equal(left, right),
+    }
+}
+
+fn equal(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (None, None) => true,
+        (Some(left), Some(right)) => left.value_eq(right),
+        _ => false,
+    }
+}
+
+fn less(left: Option<&JsonValue>, right: Option<&JsonValue>) -> bool {
+    match (left, right) {
+        (Some(JsonValue::Number(left)), Some(JsonValue::Number(right))) => {
+            left.value_cmp(right) == Some(Ordering::Less)
+        }
+        (Some(JsonValue::String(left)), Some(JsonValue::String(right))) => left < right,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn store() -> JsonValue {
+        json!({
+            "store": {
+                "book": [
+                    {"category": "reference", "author": "Nigel Rees", "title": "Sayings of the Century", "price": 8.95},
+                    {"category": "fiction", "author": "Evelyn Waugh", "title": "Sword of Honour", "price": 12.99},
+                    {"category": "fiction", "author": "Herman Melville", "title": "Moby Dick", "isbn": "0-553-21311-3", "price": 8.99},
+                    {"category": "fiction", "author": "J. R. R. Tolkien", "title": "The Lord of the Rings", "isbn": "0-395-19395-8", "price": 22.99}
+                ],
+                "bicycle": {"color": "red", "price": 399}
+            }
+        })
+    }
+
+    fn paths(value: &JsonValue, query: &str) -> Vec<String> {
+        value
+            .query(query)
+            .unwrap()
+            .iter()
+            .map(|m| m.path.to_string())
+            .collect()
+    }
+
+    fn values(value: &JsonValue, query: &str) -> JsonValue {
+        let selected = JsonPath::parse(query).unwrap().query_values(value);
+        selected.into_iter().cloned().collect()
+    }
+
+    fn count(value: &JsonValue, query: &str) -> usize {
+        JsonPath::parse(query).unwrap().query_values(value).len()
+    }
+
+    #[test]
+    fn test_rfc_store_examples() {
+        let doc = store();
+
+        assert_eq!(
+            values(&doc, "$.store.book[*].author"),
+            json!([
+                "Nigel Rees",
+                "Evelyn Waugh",
+                "Herman Melville",
+                "J. R. R. Tolkien"
+            ])
+        );
+        assert_eq!(count(&doc, "$..author"), 4);
+        assert_eq!(count(&doc, "$.store.*"), 2);
+        assert_eq!(count(&doc, "$.store..price"), 5);
+        assert_eq!(paths(&doc, "$..book[2]"), vec!["$['store']['book'][2]"]);
+        assert_eq!(
+            values(&doc, "$..book[-1].title"),
+            json!(["The Lord of the Rings"])
+        );
+        assert_eq!(
+            paths(&doc, "$..book[0,1]"),
+            vec!["$['store']['book'][0]", "$['store']['book'][1]"]
+        );
+        assert_eq!(count(&doc, "$..book[:2]"), 2);
+        assert_eq!(
+            values(&doc, "$..book[?@.isbn].title"),
+            json!(["Moby Dick", "The Lord of the Rings"])
+        );
+        assert_eq!(
+            values(&doc, "$..book[?@.price<10].title"),
+            json!(["Sayings of the Century", "Moby Dick"])
+        );
+        assert_eq!(count(&doc, "$..*"), 27);
+    }
+
+    #[test]
+    fn test_slices() {
+        let doc = json!(["a", "b", "c", "d", "e", "f", "g"]);
+
+        assert_eq!(values(&doc, "$[1:3]"), json!(["b", "c"]));
+        assert_eq!(values(&doc, "$[5:]"), json!(["f", "g"]));
+        assert_eq!(values(&doc, "$[1:5:2]"), json!(["b", "d"]));
+        assert_eq!(values(&doc, "$[5:1:-2]"), json!(["f", "d"]));
+        assert_eq!(
+            values(&doc, "$[::-1]"),
+            json!(["g", "f", "e", "d", "c", "b", "a"])
+        );
+        assert_eq!(values(&doc, "$[-2:]"), json!(["f", "g"]));
+        assert_eq!(count(&doc, "$[::0]"), 0);
+        assert_eq!(paths(&doc, "$[ 6 : 100 ]"), vec!["$[6]"]);
+    }
+
+    #[test]
+    fn test_filters_and_functions() {
+        let doc = json!({
+            "orders": [
+                {"id": "A-1", "total": 150, "items": [1, 2, 3]},
+                {"id": "B-2", "total": 99.5, "items": []},
+                {"id": "A-3", "total": 100.0, "items": [4], "note": "rush"}
+            ]
+        });
+        let ids = |query: &str| -> Vec<String> {
+            let selected = JsonPath::parse(query).unwrap().query_values(&doc);
+            selected
+                .iter()
+                .map(|order| order["id"].as_str().unwrap().to_string())
+                .collect()
+        };
+
+        assert_eq!(ids("$..orders[?@.total > 100]"), vec!["A-1"]);
+        assert_eq!(ids("$.orders[?@.total >= 100 && !@.note]"), vec!["A-1"]);
+        assert_eq!(
+            ids("$.orders[?@.total == 100 || @.id == 'B-2']"),
+            vec!["B-2", "A-3"]
+        );
+        assert_eq!(ids("$.orders[?length(@.items) >= 1]"), vec!["A-1", "A-3"]);
+        assert_eq!(ids("$.orders[?count(@.items[*]) == 0]"), vec!["B-2"]);
+        assert_eq!(
+            ids("$.orders[?match(@.id, 'A-[0-9]+')]"),
+            vec!["A-1", "A-3"]
+        );
+        assert_eq!(ids("$.orders[?search(@.id, '2')]"), vec!["B-2"]);
+        assert_eq!(ids("$.orders[?value(@..note) == \"rush\"]"), vec!["A-3"]);
+        assert_eq!(
+            ids("$.orders[?@.total < $.orders[0].total]"),
+            vec!["B-2", "A-3"]
+        );
+        assert_eq!(
+            ids("$.orders[?!(@.total > 1 && @.total < 100)]"),
+            vec!["A-1", "A-3"]
+        );
+    }
+
+    #[test]
+    fn test_comparison_semantics() {
+        let doc = json!({"obj": {"x": "y"}, "arr": [2, 3], "n": 1});
+        let holds = |expr: &str| count(&doc, &format!("$[?{}]", expr)) > 0;
+
+        // examples from RFC 9535, section 2.3.5.3
+        assert!(holds("$.absent1 == $.absent2"));
+        assert!(holds("$.absent1 <= $.absent2"));
+        assert!(!holds("$.absent == 'g'"));
+        assert!(!holds("$.absent1 != $.absent2"));
+        assert!(holds("1 <= 2"));
+        assert!(!holds("1 > 2"));
+        assert!(!holds("13 == '13'"));
+        assert!(holds("'a' <= 'b'"));
+        assert!(!holds("'a' > 'b'"));
+        assert!(holds("$.obj == $.obj"));
+        assert!(!holds("$.obj != $.obj"));
+        assert!(holds("$.arr == $.arr"));
+        assert!(!holds("$.obj == $.arr"));
+        assert!(!holds("$.obj < $.obj"));
+        assert!(holds("$.obj <= $.obj"));
+        assert!(!holds("1 <= $.arr"));
+        assert!(holds("true <= true"));
+        assert!(!holds("true > true"));
+        assert!(holds("$.n == 1.0"));
+        assert!(holds("-0 == 0"));
+    }
+
+    #[test]
+    fn test_normalized_paths() {
+        let doc = json!({"a'b": {"c\\d": [0, {"\u{1}\n": true}]}});
+        let matches = doc.query("$..[?@ == true]").unwrap();
+
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].value, &json!(true));
+        assert_eq!(
+            matches[0].path.to_string(),
+            r"$['a\'b']['c\\d'][1]['\u0001\n']"
+        );
+        assert_eq!(
+            matches[0].path.to_pointer().to_string(),
+            "/a'b/c\\d/1/\u{1}\n"
+        );
+        assert_eq!(doc.get_path(matches[0].path.segments()), Some(&json!(true)));
+    }
+
+    #[test]
+    fn test_names_and_escapes() {
+        let doc = json!({"k": 1, "é": 2, "☺": 3, "a b": 4, "'": 5, "\u{1F600}": 6});
+
+        assert_eq!(values(&doc, "$.é"), json!([2]));
+        assert_eq!(values(&doc, "$['\\u263A']"), json!([3]));
+        assert_eq!(values(&doc, "$[\"a b\"]"), json!([4]));
+        assert_eq!(values(&doc, "$['\\'', \"'\"]"), json!([5, 5]));
+        assert_eq!(values(&doc, "$['\\uD83D\\uDE00']"), json!([6]));
+        assert_eq!(values(&doc, "$ .k"), json!([1]));
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        let invalid = [
+            "",
+            "store",
+            "$.",
+            "$..",
+            "$. a",
+            "$[",
+            "$[01]",
+            "$[-0]",
+            "$[1.0]",
+            "$['a'",
+            "$.a ",
+            "$[9007199254740992]",
+            "$['\\\"']",
+            "$[\"\\'\"]",
+            "$['\\uD800']",
+            "$['\u{1}']",
+            "$[?@.a == @.*]",
+            "$[?@..a == 1]",
+            "$[?1]",
+            "$[?length(@)]",
+            "$[?count(@.*) == 1 == 2]",
+            "$[?match(@.a, 'x') == true]",
+            "$[?length(@.*) == 1]",
+            "$[?count(1) == 1]",
+            "$[?foo(@)]",
+            "$[?length(@, 1) == 1]",
+            "$[?@.a == 01]",
+            "$[?@.a == 1.]",
+            "$[?True]",
+            "$[?!@.a == 1]",
+            "$[?(@.a]",
+        ];
+
+        for query in invalid {
+            assert!(JsonPath::parse(query).is_err(), "{}", query);
+        }
+
+        let error = JsonPath::parse("$.store[?lenght(@) > 1]").unwrap_err();
+        assert_eq!(error.offset(), 9);
+        assert_eq!(error.to_string(), "unknown function `lenght` at offset 9");
+    }
+
+    #[test]
+    fn test_nesting_limit() {
+        let doc = json!([{"a": 1}]);
+        let parenthesized =
+            |depth: usize| format!("$[?{}@.a == 1{}]", "(".repeat(depth), ")".repeat(depth));
+
+        assert_eq!(count(&doc, &parenthesized(MAX_DEPTH - 1)), 1);
+        let error = JsonPath::parse(&parenthesized(MAX_DEPTH)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expression is nested too deeply at offset 103"
+        );
+        assert!(JsonPath::parse(&format!("$[?{}", "(".repeat(100_000))).is_err());
+        assert!(JsonPath::parse(&format!("${}", "[?@".repeat(100_000))).is_err());
+
+        // patterns from the document are checked the same way
+        let doc = json!([{"a": "x", "p": "(".repeat(100_000)}]);
+        assert_eq!(count(&doc, "$[?match(@.a, @.p)]"), 0);
+    }
+}
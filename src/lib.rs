@@ -1,28 +1,101 @@
+pub use decimal::{ConversionError, Decimal};
+pub use diagnostic::Diagnostic;
+pub use diff::{diff, diff_with, ArrayStrategy, Change, Diff, DiffOptions};
+pub use error::{ErrorKind, ParseError};
+pub use jsonpath::{JsonPath, JsonPathError, NormalizedPath, QueryMatch};
+pub use map::Map;
+pub use merge_patch::create_merge_patch;
+pub use options::{
+    DuplicateKeyPolicy, Escaping, Indent, KeyOrder, NumberFormat, NumberMode, ParseOptions,
+    SurrogatePolicy, WriteOptions,
+};
 use parser::Parser;
-use tokenizer::Tokenizer;
+pub use patch::{Patch, PatchError, PatchErrorKind, PatchOperation};
+pub use path::{PathError, PathErrorKind, PathSegment};
+pub use pointer::{JsonPointer, PointerError, PointerErrorKind};
+use tokenizer::{Token, Tokenizer};
+#[allow(deprecated)]
 pub use types::JsonDocument;
+pub use types::{JsonValue, Number, RecoveredDocument};
+pub use writer::{JsonWriter, WriterError, WriterErrorKind};
 
+mod decimal;
+mod diagnostic;
+mod diff;
+mod error;
+mod float;
+mod iregexp;
+mod jsonpath;
+mod macros;
+pub mod map;
+mod merge_patch;
+mod options;
 mod parser;
+mod patch;
+mod path;
+mod pointer;
+mod serializer;
 mod tokenizer;
 mod types;
+mod unicode;
+mod writer;
 
-pub fn parse_json(json_string: &str) -> Result<JsonDocument, String> {
-    let tokenizer = Tokenizer {
-        input_string: json_string,
-    };
-    let parser = Parser {
-        tokens: tokenizer.tokenize_json()?,
+/// Parses any JSON text as defined by RFC 8259, including scalar roots such as `42` or `"abc"`.
+pub fn parse_json(json_string: &str) -> Result<JsonValue, ParseError> {
+    parse_json_with(json_string, &ParseOptions::default())
+}
+
+/// Same as [`parse_json`], with explicit [`ParseOptions`].
+pub fn parse_json_with(json_string: &str, options: &ParseOptions) -> Result<JsonValue, ParseError> {
+    let tokenizer = Tokenizer::with_options(json_string, options.clone());
+    let parser = Parser::with_options(json_string, tokenizer.tokenize_json()?, options.clone());
+
+    parser.parse_tokens()
+}
+
+/// Parses a JSON text without stopping at the first error.
+///
+/// The parser resynchronises at commas and closing brackets, so every problem in the input is
+/// reported in one pass. Values that could not be read are replaced by `JsonValue::Null` in the
+/// returned document.
+pub fn parse_json_recovering(json_string: &str) -> RecoveredDocument {
+    parse_json_recovering_with(json_string, &ParseOptions::default())
+}
+
+/// Same as [`parse_json_recovering`], with explicit [`ParseOptions`].
+pub fn parse_json_recovering_with(json_string: &str, options: &ParseOptions) -> RecoveredDocument {
+    let mut tokenizer = Tokenizer::with_options(json_string, options.clone());
+    tokenizer.recover = true;
+    // a recovering tokenizer records its errors instead of returning them
+    let tokens = tokenizer.tokenize_json().unwrap_or_default();
+
+    let mut parser = Parser::with_options(json_string, tokens, options.clone());
+    parser.recover = true;
+    let value = parser.parse_tokens().ok();
+
+    // a root that could not be read at all only comes back as a placeholder
+    let document = match parser.tokens.first() {
+        Some((
+            Token::LeftBrace
+            | Token::LeftBracket
+            | Token::String(_)
+            | Token::Number(_)
+            | Token::Boolean(_)
+            | Token::Null,
+            _,
+        )) => value,
+        _ => None,
     };
 
-    return parser.parse_tokens();
+    let mut errors = tokenizer.errors.into_inner();
+    errors.extend(parser.errors.into_inner());
+    errors.sort_by_key(|error| error.offset());
+
+    RecoveredDocument { document, errors }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashMap;
-
-    use types::JsonValue;
-
     use super::*;
 
     #[test]
@@ -33,10 +106,7 @@ mod tests {
             Ok(result) => {
                 println!("result is {:?}", result);
 
-                let mut object_hash_map = HashMap::new();
-                object_hash_map.insert("foo".to_string(), JsonValue::String("bar".to_string()));
-
-                assert_eq!(result, JsonDocument::Object(object_hash_map))
+                assert_eq!(result, json!({"foo": "bar"}))
             }
             Err(e) => panic!("Expect success json parsing, with error {:?}", e),
         }
@@ -48,13 +118,31 @@ mod tests {
 
         match parse_json(json_string) {
             Ok(result) => {
-                let mut object_hash_map = HashMap::new();
-                object_hash_map.insert("message".to_string(), JsonValue::String("Hello \"World\"\nNew line".to_string()));
-                object_hash_map.insert("number".to_string(), JsonValue::Number(types::Number::Int(42)));
+                let expected = json!({"message": "Hello \"World\"\nNew line", "number": 42});
 
-                assert_eq!(result, JsonDocument::Object(object_hash_map))
+                assert_eq!(result, expected)
             }
-            Err(e) => panic!("Expect success json parsing with escape sequences, with error {:?}", e),
+            Err(e) => panic!(
+                "Expect success json parsing with escape sequences, with error {:?}",
+                e
+            ),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_preserves_key_order() {
+        let json_string = r#"{"zebra": 1, "apple": 2, "mango": {"y": 3, "x": 4}}"#;
+        let document = parse_json(json_string).unwrap();
+        let object = document.as_object().unwrap();
+
+        let keys: Vec<&String> = object.keys().collect();
+        assert_eq!(keys, vec!["zebra", "apple", "mango"]);
+        match object.get("mango") {
+            Some(JsonValue::Object(inner)) => {
+                let keys: Vec<&String> = inner.keys().collect();
+                assert_eq!(keys, vec!["y", "x"]);
+            }
+            other => panic!("Expect mango to be an object, got {:?}", other),
         }
     }
 
@@ -64,15 +152,176 @@ mod tests {
 
         match parse_json(json_string) {
             Ok(result) => {
-                let expected_array = vec![
-                    JsonValue::Number(types::Number::Int(1)),
-                    JsonValue::Number(types::Number::Int(2)),
-                    JsonValue::Number(types::Number::Int(3)),
-                ];
+                assert_eq!(result, json!([1, 2, 3]))
+            }
+            Err(e) => panic!(
+                "Expect success json parsing array with numbers, with error {:?}",
+                e
+            ),
+        }
+    }
+
+    #[test]
+    fn test_parse_json_with_scalar_root() {
+        assert_eq!(
+            parse_json(r#""abc""#),
+            Ok(JsonValue::String("abc".to_string()))
+        );
+        assert_eq!(parse_json(" true "), Ok(JsonValue::Boolean(true)));
+        assert_eq!(
+            parse_json("-1.5e2"),
+            Ok(JsonValue::Number(types::Number::Float(-150.0)))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_rejects_trailing_data() {
+        assert!(parse_json("{} {}").is_err());
+        assert!(parse_json("[1]]").is_err());
+    }
+
+    #[test]
+    fn test_parse_json_error_position() {
+        let json_string = "{\n  \"name\": \"nail\",\n  \"size\": 12.\n}";
+
+        match parse_json(json_string) {
+            Ok(_) => panic!("Expect error for invalid number"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::InvalidNumber);
+                assert_eq!((err.line(), err.column()), (3, 11));
+                assert_eq!(
+                    err.to_string(),
+                    "Invalid number, expected digit at line 3, column 11"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_json_recovering_reports_all_errors() {
+        let json_string =
+            "{\n  \"name\": 'nail',\n  \"tags\": [1, 2,],\n  \"size\": 01,\n  \"ok\": true\n}";
+        let recovered = parse_json_recovering(json_string);
+
+        let kinds: Vec<ErrorKind> = recovered.errors.iter().map(|error| error.kind()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                ErrorKind::UnexpectedCharacter,
+                ErrorKind::UnexpectedToken,
+                ErrorKind::InvalidNumber
+            ]
+        );
+        let lines: Vec<usize> = recovered.errors.iter().map(|error| error.line()).collect();
+        assert_eq!(lines, vec![2, 3, 4]);
+
+        assert_eq!(
+            recovered.document,
+            Some(json!({"name": null, "tags": [1, 2], "size": null, "ok": true}))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_recovering_missing_separators() {
+        let recovered = parse_json_recovering(r#"{"a" 1 "b": [1 2 : 3], "c": {"d": }"#);
+
+        let messages: Vec<String> = recovered
+            .errors
+            .iter()
+            .map(|error| error.to_string())
+            .collect();
+        assert_eq!(
+            messages,
+            vec![
+                "Unexpected token, expected ':' at line 1, column 6",
+                "Unexpected token, expected ',' or '}' at line 1, column 8",
+                "Unexpected token, expected ',' or ']' at line 1, column 16",
+                "Unexpected token, expected ',' or ']' at line 1, column 18",
+                "Unexpected token, expected value at line 1, column 35",
+                "Unexpected end of input, expected ',' or '}' at line 1, column 36",
+            ]
+        );
+
+        let document = recovered.document.expect("Expect a partial document");
+        let object = document.as_object().expect("Expect an object");
+        assert_eq!(
+            object.get("a"),
+            Some(&JsonValue::Number(types::Number::Int(1)))
+        );
+        assert_eq!(object.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_json_recovering_valid_input() {
+        let recovered = parse_json_recovering(r#"[true, null]"#);
+
+        assert!(recovered.errors.is_empty());
+        assert_eq!(
+            recovered.document,
+            Some(JsonValue::Array(vec![
+                JsonValue::Boolean(true),
+                JsonValue::Null
+            ]))
+        );
+    }
+
+    #[test]
+    fn test_parse_json_recovering_scalar_root() {
+        let recovered = parse_json_recovering("42");
+        assert!(recovered.is_valid());
+        assert_eq!(
+            recovered.document,
+            Some(JsonValue::Number(types::Number::Int(42)))
+        );
+
+        let recovered = parse_json_recovering("null x");
+        assert_eq!(recovered.document, Some(JsonValue::Null));
+        assert_eq!(recovered.errors.len(), 1);
+
+        let recovered = parse_json_recovering("'abc'");
+        assert_eq!(recovered.document, None);
+        assert_eq!(recovered.errors.len(), 1);
+    }
+
+    #[test]
+    fn test_parse_json_recovering_deep_nesting() {
+        let options = ParseOptions {
+            max_depth: 2,
+            ..ParseOptions::default()
+        };
+        let recovered = parse_json_recovering_with("[[1, [2, [3]], 4], 5, [6]]", &options);
+
+        assert_eq!(recovered.errors.len(), 1);
+        assert_eq!(recovered.errors[0].kind(), ErrorKind::NestingTooDeep);
+        assert_eq!(recovered.errors[0].offset(), 5);
+        assert_eq!(recovered.document, Some(json!([[1, null, 4], 5, [6]])));
+
+        let recovered = parse_json_recovering(&"[".repeat(100_000));
+        assert_eq!(recovered.errors[0].kind(), ErrorKind::NestingTooDeep);
+    }
+
+    #[test]
+    fn test_parse_json_with_exact_numbers() {
+        let options = ParseOptions {
+            number_mode: NumberMode::Exact,
+            ..ParseOptions::default()
+        };
+        let json_string = r#"{"price": 0.1000000000000000055511151231257827, "big": 1e400}"#;
 
-                assert_eq!(result, JsonDocument::Array(expected_array))
+        match parse_json_with(json_string, &options) {
+            Ok(JsonValue::Object(object)) => {
+                let price = match object.get("price") {
+                    Some(JsonValue::Number(types::Number::Decimal(d))) => d,
+                    other => panic!("Expect a decimal, got {:?}", other),
+                };
+                assert_eq!(price.as_str(), "0.1000000000000000055511151231257827");
+                assert_ne!(price, &"0.1".parse::<Decimal>().unwrap());
+                assert!(
+                    object["big"]
+                        == JsonValue::Number(types::Number::Decimal("10e399".parse().unwrap()))
+                );
             }
-            Err(e) => panic!("Expect success json parsing array with numbers, with error {:?}", e),
+            other => panic!("Expect an object, got {:?}", other),
         }
     }
 }
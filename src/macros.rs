@@ -0,0 +1,265 @@
+/// Builds a [`JsonValue`](crate::JsonValue) from JSON-like syntax.
+///
+/// Any Rust expression can be interpolated where a value or a key is expected. Values are
+/// converted with `JsonValue::from`, so numbers, strings, booleans, `Option`s, `Vec`s, `Map`s and
+/// other `JsonValue`s can be used directly; keys can be anything that converts into a `String`.
+///
+/// ```
+/// use nail::json;
+///
+/// let id = 7;
+/// let tags = vec!["a", "b"];
+/// let value = json!({
+///     "id": id,
+///     "tags": tags,
+///     "owner": {"name": "Ada", "admin": true},
+///     "parent": null,
+///     "scores": [1, 2.5, -3],
+/// });
+///
+/// assert_eq!(value["owner"]["name"].as_str(), Some("Ada"));
+/// assert_eq!(value["tags"][1].as_str(), Some("b"));
+/// ```
+#[macro_export]
+macro_rules! json {
+    ($($json:tt)+) => {
+        $crate::json_internal!($($json)+)
+    };
+}
+
+/// The token muncher behind [`json!`]. Arrays and objects are consumed one token tree at a time,
+/// as a comma-separated expression cannot be matched in one go when it contains nested syntax.
+#[macro_export]
+#[doc(hidden)]
+macro_rules! json_internal {
+    // array elements, accumulated as expressions in square brackets
+
+    // done, with or without a trailing comma
+    (@array [$($elems:expr,)*]) => {
+        ::std::vec![$($elems,)*]
+    };
+    (@array [$($elems:expr),*]) => {
+        ::std::vec![$($elems),*]
+    };
+
+    // the next element is a literal, an array or an object
+    (@array [$($elems:expr,)*] null $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(null)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] true $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(true)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] false $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!(false)] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] [$($array:tt)*] $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!([$($array)*])] $($rest)*)
+    };
+    (@array [$($elems:expr,)*] {$($object:tt)*} $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!({$($object)*})] $($rest)*)
+    };
+
+    // the next element is an expression followed by a comma
+    (@array [$($elems:expr,)*] $next:expr, $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($next),] $($rest)*)
+    };
+
+    // the last element is an expression without a trailing comma
+    (@array [$($elems:expr,)*] $last:expr) => {
+        $crate::json_internal!(@array [$($elems,)* $crate::json_internal!($last)])
+    };
+
+    // the comma after the most recent element
+    (@array [$($elems:expr),*] , $($rest:tt)*) => {
+        $crate::json_internal!(@array [$($elems,)*] $($rest)*)
+    };
+
+    // anything else is a syntax error, reported on the offending token
+    (@array [$($elems:expr),*] $unexpected:tt $($rest:tt)*) => {
+        $crate::json_internal!(@unexpected $unexpected)
+    };
+
+    // object members, inserted into `$object` one at a time; the key is munched in parentheses
+    // and the remaining input is kept twice, the copy being used to report errors
+
+    // done
+    (@object $object:ident () () ()) => {};
+
+    // insert the current member, then continue after its comma
+    (@object $object:ident [$($key:tt)+] ($value:expr) , $($rest:tt)*) => {
+        let _ = $object.insert(($($key)+), $value);
+        $crate::json_internal!(@object $object () ($($rest)*) ($($rest)*));
+    };
+
+    // a member without a trailing comma must be the last one
+    (@object $object:ident [$($key:tt)+] ($value:expr) $unexpected:tt $($rest:tt)*) => {
+        $crate::json_internal!(@unexpected $unexpected);
+    };
+
+    // insert the last member
+    (@object $object:ident [$($key:tt)+] ($value:expr)) => {
+        let _ = $object.insert(($($key)+), $value);
+    };
+
+    // the value is a literal, an array or an object
+    (@object $object:ident ($($key:tt)+) (: null $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(null)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: true $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(true)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: false $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!(false)) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: [$($array:tt)*] $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!([$($array)*])) $($rest)*);
+    };
+    (@object $object:ident ($($key:tt)+) (: {$($map:tt)*} $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!({$($map)*})) $($rest)*);
+    };
+
+    // the value is an expression followed by a comma
+    (@object $object:ident ($($key:tt)+) (: $value:expr , $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)) , $($rest)*);
+    };
+
+    // the last value is an expression without a trailing comma
+    (@object $object:ident ($($key:tt)+) (: $value:expr) $copy:tt) => {
+        $crate::json_internal!(@object $object [$($key)+] ($crate::json_internal!($value)));
+    };
+
+    // a key with a ':' but no value, or with neither
+    (@object $object:ident ($($key:tt)+) (:) $copy:tt) => {
+        $crate::json_internal!();
+    };
+    (@object $object:ident ($($key:tt)+) () $copy:tt) => {
+        $crate::json_internal!();
+    };
+
+    // a ':' without a key
+    (@object $object:ident () (: $($rest:tt)*) ($colon:tt $($copy:tt)*)) => {
+        $crate::json_internal!(@unexpected $colon);
+    };
+
+    // a ',' inside a key, i.e. a member without a value
+    (@object $object:ident ($($key:tt)*) (, $($rest:tt)*) ($comma:tt $($copy:tt)*)) => {
+        $crate::json_internal!(@unexpected $comma);
+    };
+
+    // a parenthesized key is taken as a whole expression
+    (@object $object:ident () (($key:expr) : $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($key) (: $($rest)*) (: $($rest)*));
+    };
+
+    // munch one more token into the key
+    (@object $object:ident ($($key:tt)*) ($tt:tt $($rest:tt)*) $copy:tt) => {
+        $crate::json_internal!(@object $object ($($key)* $tt) ($($rest)*) ($($rest)*));
+    };
+
+    // matches no input, so passing a token reports it as unexpected
+    (@unexpected) => {};
+
+    // entry points
+
+    (null) => {
+        $crate::JsonValue::Null
+    };
+
+    (true) => {
+        $crate::JsonValue::Boolean(true)
+    };
+
+    (false) => {
+        $crate::JsonValue::Boolean(false)
+    };
+
+    ([]) => {
+        $crate::JsonValue::Array(::std::vec::Vec::new())
+    };
+
+    ([ $($tt:tt)+ ]) => {
+        $crate::JsonValue::Array($crate::json_internal!(@array [] $($tt)+))
+    };
+
+    ({}) => {
+        $crate::JsonValue::Object($crate::Map::new())
+    };
+
+    ({ $($tt:tt)+ }) => {{
+        let mut object = $crate::Map::new();
+        $crate::json_internal!(@object object () ($($tt)+) ($($tt)+));
+        $crate::JsonValue::Object(object)
+    }};
+
+    // any other expression is converted with `From`
+    ($other:expr) => {
+        $crate::JsonValue::from($other)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{JsonValue, Map, Number};
+
+    #[test]
+    fn test_json_literals() {
+        assert_eq!(json!(null), JsonValue::Null);
+        assert_eq!(json!(true), JsonValue::Boolean(true));
+        assert_eq!(json!(-12), JsonValue::Number(Number::Int(-12)));
+        assert_eq!(json!(2.5), JsonValue::Number(Number::Float(2.5)));
+        assert_eq!(json!("text"), JsonValue::String("text".to_string()));
+        assert_eq!(json!([]), JsonValue::Array(Vec::new()));
+        assert_eq!(json!({}), JsonValue::Object(Map::new()));
+    }
+
+    #[test]
+    fn test_json_matches_parsed_text() {
+        let value = json!({
+            "name": "nail",
+            "version": [0, 1, 0],
+            "nested": {"empty": [], "flags": [true, false, null], "deep": [[1], {"a": {}}]},
+            "ratio": -0.25,
+        });
+        let parsed = crate::parse_json(
+            r#"{"name": "nail", "version": [0, 1, 0], "nested": {"empty": [], "flags": [true, false, null], "deep": [[1], {"a": {}}]}, "ratio": -0.25}"#,
+        )
+        .unwrap();
+
+        assert_eq!(value, parsed);
+    }
+
+    #[test]
+    fn test_json_interpolates_expressions() {
+        let id = 7u64;
+        let tags = vec!["a", "b"];
+        let missing: Option<&str> = None;
+        let key = String::from("dynamic");
+        let inner = json!({"x": 1});
+
+        let value = json!({
+            "id": id,
+            "tags": tags.clone(),
+            "missing": missing,
+            (key.clone()): id * 2,
+            "inner": inner,
+            "sum": 1 + 2,
+            "list": [id, "two", tags.len()]
+        });
+
+        assert_eq!(value["id"].as_u64(), Some(7));
+        assert_eq!(value["tags"][1].as_str(), Some("b"));
+        assert!(value["missing"].is_null());
+        assert_eq!(value["dynamic"].as_i64(), Some(14));
+        assert_eq!(value["inner"]["x"].as_i64(), Some(1));
+        assert_eq!(value["sum"].as_i64(), Some(3));
+        assert_eq!(value["list"], json!([7, "two", 2]));
+    }
+
+    #[test]
+    fn test_json_keeps_member_order() {
+        let value = json!({"z": 1, "a": 2, "m": 3});
+        let keys: Vec<&String> = value.as_object().unwrap().keys().collect();
+
+        assert_eq!(keys, vec!["z", "a", "m"]);
+    }
+}
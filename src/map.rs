@@ -0,0 +1,556 @@
+use std::{collections::HashMap, fmt, ops::Index, slice, vec};
+
+use crate::types::JsonValue;
+
+/// The members of a JSON object, in insertion order.
+///
+/// Lookups by key go through a hash index, so they stay O(1) however large the object is.
+/// Iteration, formatting and serialization follow the order in which keys were first inserted,
+/// which for a parsed object is the order of the source text. Two maps are equal when they hold
+/// the same members, whatever their order.
+///
+/// A key normally appears once. [`append`](Map::append) can add further members with the same
+/// key, as parsing with `DuplicateKeyPolicy::KeepAll` does; lookups then see the first one and
+/// [`get_all`](Map::get_all) returns them all.
+#[derive(Clone, Default)]
+pub struct Map {
+    entries: Vec<(String, JsonValue)>,
+    /// Position of the first member with each key.
+    index: HashMap<String, usize>,
+}
+
+impl Map {
+    pub fn new() -> Self {
+        Map::default()
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Map {
+            entries: Vec::with_capacity(capacity),
+            index: HashMap::with_capacity(capacity),
+        }
+    }
+
+    /// The number of members, counting every occurrence of a repeated key.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.index.get(key).map(|&i| &self.entries[i].1)
+    }
+
+    /// Like [`get`](Map::get), also returning the stored key.
+    pub fn get_key_value(&self, key: &str) -> Option<(&String, &JsonValue)> {
+        self.index.get(key).map(|&i| {
+            let (key, value) = &self.entries[i];
+            (key, value)
+        })
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+        match self.index.get(key) {
+            Some(&i) => Some(&mut self.entries[i].1),
+            None => None,
+        }
+    }
+
+    /// Every value of `key`, in insertion order; more than one only for keys added with
+    /// [`append`](Map::append).
+    pub fn get_all<'a>(&'a self, key: &'a str) -> impl Iterator<Item = &'a JsonValue> + 'a {
+        let first = self.index.get(key).copied().unwrap_or(self.entries.len());
+        // without repeated keys only the first occurrence needs to be looked at
+        let end = match self.has_duplicates() {
+            true => self.entries.len(),
+            false => (first + 1).min(self.entries.len()),
+        };
+
+        self.entries[first..end]
+            .iter()
+            .filter(move |(k, _)| k == key)
+            .map(|(_, value)| value)
+    }
+
+    /// Inserts a member, returning the previous value of `key` if there was one.
+    ///
+    /// A key that is already present keeps its position, only its value is replaced. Any further
+    /// occurrences added with [`append`](Map::append) are removed.
+    pub fn insert(&mut self, key: impl Into<String>, value: JsonValue) -> Option<JsonValue> {
+        let key = key.into();
+        match self.index.get(&key) {
+            Some(&i) => {
+                let previous = std::mem::replace(&mut self.entries[i].1, value);
+                if self.has_duplicates() {
+                    self.remove_duplicates_after(i);
+                }
+                Some(previous)
+            }
+            None => {
+                self.index.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+                None
+            }
+        }
+    }
+
+    /// Adds a member at the end, even if `key` is already present.
+    pub fn append(&mut self, key: impl Into<String>, value: JsonValue) {
+        let key = key.into();
+        if !self.index.contains_key(&key) {
+            self.index.insert(key.clone(), self.entries.len());
+        }
+        self.entries.push((key, value));
+    }
+
+    /// Removes a member and returns its value, keeping the order of the remaining members.
+    ///
+    /// This shifts the members that follow it, so it takes O(n) time. Every occurrence of a
+    /// repeated key is removed, and the first value is returned.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        self.remove_entry(key).map(|(_, value)| value)
+    }
+
+    /// Removes a member and returns its key and value, keeping the order of the remaining members.
+    pub fn remove_entry(&mut self, key: &str) -> Option<(String, JsonValue)> {
+        let removed = *self.index.get(key)?;
+        if self.has_duplicates() {
+            self.remove_duplicates_after(removed);
+        }
+
+        self.index.remove(key);
+        for i in self.index.values_mut() {
+            if *i > removed {
+                *i -= 1;
+            }
+        }
+        Some(self.entries.remove(removed))
+    }
+
+    fn has_duplicates(&self) -> bool {
+        self.entries.len() > self.index.len()
+    }
+
+    /// Removes the later occurrences of the key at `first` and rebuilds the index.
+    fn remove_duplicates_after(&mut self, first: usize) {
+        let key = self.entries[first].0.clone();
+        let mut position = 0;
+        self.entries.retain(|(k, _)| {
+            position += 1;
+            position - 1 <= first || *k != key
+        });
+
+        self.index.clear();
+        for (i, (k, _)) in self.entries.iter().enumerate() {
+            self.index.entry(k.clone()).or_insert(i);
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.index.clear();
+    }
+
+    /// Gets the entry for `key`, to inspect or modify it in place.
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        let key = key.into();
+        match self.index.get(&key) {
+            Some(&index) => Entry::Occupied(OccupiedEntry { map: self, index }),
+            None => Entry::Vacant(VacantEntry { map: self, key }),
+        }
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            inner: self.entries.iter(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut {
+            inner: self.entries.iter_mut(),
+        }
+    }
+
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &String> + ExactSizeIterator {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl DoubleEndedIterator<Item = &JsonValue> + ExactSizeIterator {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(
+        &mut self,
+    ) -> impl DoubleEndedIterator<Item = &mut JsonValue> + ExactSizeIterator {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// A view into a single member of a [`Map`], obtained with [`Map::entry`].
+pub enum Entry<'a> {
+    Occupied(OccupiedEntry<'a>),
+    Vacant(VacantEntry<'a>),
+}
+
+pub struct OccupiedEntry<'a> {
+    map: &'a mut Map,
+    index: usize,
+}
+
+pub struct VacantEntry<'a> {
+    map: &'a mut Map,
+    key: String,
+}
+
+impl<'a> Entry<'a> {
+    pub fn key(&self) -> &str {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    /// Returns the value, inserting `default` at the end of the map if the key is missing.
+    pub fn or_insert(self, default: JsonValue) -> &'a mut JsonValue {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default),
+        }
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> JsonValue>(self, default: F) -> &'a mut JsonValue {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    /// Applies `f` to the value if the key is present.
+    pub fn and_modify<F: FnOnce(&mut JsonValue)>(mut self, f: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            f(entry.get_mut());
+        }
+        self
+    }
+}
+
+impl<'a> OccupiedEntry<'a> {
+    pub fn key(&self) -> &str {
+        &self.map.entries[self.index].0
+    }
+
+    pub fn get(&self) -> &JsonValue {
+        &self.map.entries[self.index].1
+    }
+
+    pub fn get_mut(&mut self) -> &mut JsonValue {
+        &mut self.map.entries[self.index].1
+    }
+
+    pub fn into_mut(self) -> &'a mut JsonValue {
+        &mut self.map.entries[self.index].1
+    }
+
+    /// Replaces the value, returning the previous one.
+    pub fn insert(&mut self, value: JsonValue) -> JsonValue {
+        std::mem::replace(self.get_mut(), value)
+    }
+
+    /// Removes the member, keeping the order of the remaining members.
+    pub fn remove(self) -> JsonValue {
+        let key = self.map.entries[self.index].0.clone();
+        self.map.remove(&key).unwrap_or(JsonValue::Null)
+    }
+}
+
+impl<'a> VacantEntry<'a> {
+    pub fn key(&self) -> &str {
+        &self.key
+    }
+
+    /// Inserts the value at the end of the map.
+    pub fn insert(self, value: JsonValue) -> &'a mut JsonValue {
+        let index = self.map.entries.len();
+        self.map.index.insert(self.key.clone(), index);
+        self.map.entries.push((self.key, value));
+        &mut self.map.entries[index].1
+    }
+}
+
+impl PartialEq for Map {
+    fn eq(&self, other: &Self) -> bool {
+        if self.len() != other.len() {
+            return false;
+        }
+        if !self.has_duplicates() && !other.has_duplicates() {
+            return self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value));
+        }
+
+        // repeated keys must have the same values in the same order
+        self.index.len() == other.index.len()
+            && self
+                .index
+                .keys()
+                .all(|key| self.get_all(key).eq(other.get_all(key)))
+    }
+}
+
+impl Index<&str> for Map {
+    type Output = JsonValue;
+
+    /// Returns the value of `key`, panicking if it is missing.
+    fn index(&self, key: &str) -> &JsonValue {
+        self.get(key)
+            .unwrap_or_else(|| panic!("key {:?} not found in map", key))
+    }
+}
+
+impl fmt::Debug for Map {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Into<String>> FromIterator<(K, JsonValue)> for Map {
+    fn from_iter<I: IntoIterator<Item = (K, JsonValue)>>(iter: I) -> Self {
+        let mut map = Map::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Into<String>> Extend<(K, JsonValue)> for Map {
+    fn extend<I: IntoIterator<Item = (K, JsonValue)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+/// Iterator over the members of a [`Map`], in insertion order.
+pub struct Iter<'a> {
+    inner: slice::Iter<'a, (String, JsonValue)>,
+}
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (&'a String, &'a JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Iter<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// Mutable iterator over the members of a [`Map`], in insertion order.
+pub struct IterMut<'a> {
+    inner: slice::IterMut<'a, (String, JsonValue)>,
+}
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (&'a String, &'a mut JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|(key, value)| (&*key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IterMut<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back().map(|(key, value)| (&*key, value))
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+/// Owning iterator over the members of a [`Map`], in insertion order.
+pub struct IntoIter {
+    inner: vec::IntoIter<(String, JsonValue)>,
+}
+
+impl Iterator for IntoIter {
+    type Item = (String, JsonValue);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for IntoIter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.inner.next_back()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl IntoIterator for Map {
+    type Item = (String, JsonValue);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        IntoIter {
+            inner: self.entries.into_iter(),
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Map {
+    type Item = (&'a String, &'a JsonValue);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Map {
+    type Item = (&'a String, &'a mut JsonValue);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> IterMut<'a> {
+        self.iter_mut()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Number;
+
+    fn int(i: i64) -> JsonValue {
+        JsonValue::Number(Number::Int(i))
+    }
+
+    fn keys(map: &Map) -> Vec<&str> {
+        map.keys().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn test_map_keeps_insertion_order() {
+        let mut map = Map::new();
+        map.insert("zebra", int(1));
+        map.insert("apple", int(2));
+        map.insert("mango", int(3));
+
+        assert_eq!(keys(&map), vec!["zebra", "apple", "mango"]);
+        assert_eq!(map.get("apple"), Some(&int(2)));
+        assert_eq!(map.get("kiwi"), None);
+    }
+
+    #[test]
+    fn test_map_insert_existing_key_keeps_position() {
+        let mut map: Map = [("a", int(1)), ("b", int(2))].into_iter().collect();
+
+        assert_eq!(map.insert("a", int(3)), Some(int(1)));
+        assert_eq!(keys(&map), vec!["a", "b"]);
+        assert_eq!(map.get("a"), Some(&int(3)));
+    }
+
+    #[test]
+    fn test_map_remove_keeps_order_and_index() {
+        let mut map: Map = [("a", int(1)), ("b", int(2)), ("c", int(3)), ("d", int(4))]
+            .into_iter()
+            .collect();
+
+        assert_eq!(map.remove("b"), Some(int(2)));
+        assert_eq!(map.remove("b"), None);
+        assert_eq!(keys(&map), vec!["a", "c", "d"]);
+        assert_eq!(map.get("c"), Some(&int(3)));
+        assert_eq!(map.get("d"), Some(&int(4)));
+
+        map.insert("b", int(5));
+        assert_eq!(keys(&map), vec!["a", "c", "d", "b"]);
+    }
+
+    #[test]
+    fn test_map_entry() {
+        let mut map = Map::new();
+        *map.entry("count").or_insert(int(0)) = int(1);
+        map.entry("count").and_modify(|value| *value = int(2));
+        map.entry("other").or_insert_with(|| JsonValue::Null);
+
+        assert_eq!(map.get("count"), Some(&int(2)));
+        assert_eq!(keys(&map), vec!["count", "other"]);
+
+        match map.entry("count") {
+            Entry::Occupied(entry) => assert_eq!(entry.remove(), int(2)),
+            Entry::Vacant(_) => panic!("Expect count to be present"),
+        }
+        assert_eq!(keys(&map), vec!["other"]);
+        assert_eq!(map.get("other"), Some(&JsonValue::Null));
+    }
+
+    #[test]
+    fn test_map_equality_ignores_order() {
+        let a: Map = [("a", int(1)), ("b", int(2))].into_iter().collect();
+        let b: Map = [("b", int(2)), ("a", int(1))].into_iter().collect();
+        let c: Map = [("a", int(1)), ("b", int(3))].into_iter().collect();
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn test_map_append_keeps_every_occurrence() {
+        let mut map = Map::new();
+        map.insert("a", int(1));
+        map.insert("b", int(2));
+        map.append("a", int(3));
+
+        assert_eq!(map.len(), 3);
+        assert_eq!(keys(&map), vec!["a", "b", "a"]);
+        assert_eq!(map.get("a"), Some(&int(1)));
+        assert_eq!(map.get_all("a").collect::<Vec<_>>(), vec![&int(1), &int(3)]);
+        assert_eq!(map.get_all("b").collect::<Vec<_>>(), vec![&int(2)]);
+        assert_eq!(map.get_all("c").count(), 0);
+
+        let reordered: Map = [("b", int(2)), ("a", int(1))].into_iter().collect();
+        assert_ne!(map, reordered);
+
+        assert_eq!(map.remove("a"), Some(int(1)));
+        assert_eq!(keys(&map), vec!["b"]);
+    }
+
+    #[test]
+    fn test_map_insert_replaces_every_occurrence() {
+        let mut map = Map::new();
+        map.append("a", int(1));
+        map.append("b", int(2));
+        map.append("a", int(3));
+        map.append("c", int(4));
+
+        assert_eq!(map.insert("a", int(5)), Some(int(1)));
+        assert_eq!(keys(&map), vec!["a", "b", "c"]);
+        assert_eq!(map.get("c"), Some(&int(4)));
+        assert_eq!(map.get_all("a").collect::<Vec<_>>(), vec![&int(5)]);
+    }
+}
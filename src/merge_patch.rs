@@ -0,0 +1,174 @@
+use crate::{map::Map, types::JsonValue};
+
+impl JsonValue {
+    /// Applies a JSON Merge Patch (RFC 7386), as sent with `application/merge-patch+json`.
+    ///
+    /// An object patch is merged member by member: `null` removes the member, an object is
+    /// merged recursively and any other value replaces the member. Any other patch, arrays
+    /// included, replaces the whole value.
+    pub fn apply_merge_patch(&mut self, patch: &JsonValue) {
+        let members = match patch {
+            JsonValue::Object(members) => members,
+            _ => {
+                *self = patch.clone();
+                return;
+            }
+        };
+
+        if !matches!(self, JsonValue::Object(_)) {
+            *self = JsonValue::Object(Map::new());
+        }
+        if let JsonValue::Object(obj) = self {
+            for (key, value) in members {
+                if value.is_null() {
+                    obj.remove(key);
+                } else {
+                    obj.entry(key.as_str())
+                        .or_insert(JsonValue::Null)
+                        .apply_merge_patch(value);
+                }
+            }
+        }
+    }
+}
+
+/// Computes the merge patch that turns `source` into `target`, the reverse of
+/// [`JsonValue::apply_merge_patch`].
+///
+/// Members are compared recursively, so only the changed ones are in the patch. Merge patches
+/// cannot set a member to `null`, as `null` means removal: a `null` member of `target` ends up
+/// removed when the patch is applied.
+pub fn create_merge_patch(source: &JsonValue, target: &JsonValue) -> JsonValue {
+    let (source, target) = match (source, target) {
+        (JsonValue::Object(source), JsonValue::Object(target)) => (source, target),
+        _ => return target.clone(),
+    };
+    let mut patch = Map::new();
+
+    for (key, value) in target {
+        match source.get(key) {
+            Some(previous) if previous == value => {}
+            Some(previous) => {
+                patch.insert(key.as_str(), create_merge_patch(previous, value));
+            }
+            None => {
+                patch.insert(key.as_str(), value.clone());
+            }
+        }
+    }
+    for key in source.keys() {
+        if !target.contains_key(key) {
+            patch.insert(key.as_str(), JsonValue::Null);
+        }
+    }
+
+    JsonValue::Object(patch)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn rfc_examples() -> Vec<(JsonValue, JsonValue, JsonValue)> {
+        vec![
+            (json!({"a": "b"}), json!({"a": "c"}), json!({"a": "c"})),
+            (
+                json!({"a": "b"}),
+                json!({"b": "c"}),
+                json!({"a": "b", "b": "c"}),
+            ),
+            (json!({"a": "b"}), json!({"a": null}), json!({})),
+            (
+                json!({"a": "b", "b": "c"}),
+                json!({"a": null}),
+                json!({"b": "c"}),
+            ),
+            (json!({"a": ["b"]}), json!({"a": "c"}), json!({"a": "c"})),
+            (json!({"a": "c"}), json!({"a": ["b"]}), json!({"a": ["b"]})),
+            (
+                json!({"a": {"b": "c"}}),
+                json!({"a": {"b": "d", "c": null}}),
+                json!({"a": {"b": "d"}}),
+            ),
+            (
+                json!({"a": [{"b": "c"}]}),
+                json!({"a": [1]}),
+                json!({"a": [1]}),
+            ),
+            (json!(["a", "b"]), json!(["c", "d"]), json!(["c", "d"])),
+            (json!({"a": "b"}), json!(["c"]), json!(["c"])),
+            (json!({"a": "foo"}), json!(null), json!(null)),
+            (json!({"a": "foo"}), json!("bar"), json!("bar")),
+            (
+                json!({"e": null}),
+                json!({"a": 1}),
+                json!({"e": null, "a": 1}),
+            ),
+            (
+                json!([1, 2]),
+                json!({"a": "b", "c": null}),
+                json!({"a": "b"}),
+            ),
+            (
+                json!({}),
+                json!({"a": {"bb": {"ccc": null}}}),
+                json!({"a": {"bb": {}}}),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_apply_rfc_examples() {
+        for (original, patch, expected) in rfc_examples() {
+            let mut value = original.clone();
+            value.apply_merge_patch(&patch);
+            assert_eq!(value, expected, "{:?} patched with {:?}", original, patch);
+        }
+    }
+
+    #[test]
+    fn test_create_merge_patch() {
+        let source = json!({
+            "title": "Goodbye!",
+            "author": {"givenName": "John", "familyName": "Doe"},
+            "tags": ["example", "sample"],
+            "content": "This will be unchanged"
+        });
+        let target = json!({
+            "title": "Hello!",
+            "author": {"givenName": "John"},
+            "tags": ["example"],
+            "content": "This will be unchanged",
+            "phoneNumber": "+01-123-456-7890"
+        });
+
+        let patch = create_merge_patch(&source, &target);
+
+        assert_eq!(
+            patch,
+            json!({
+                "title": "Hello!",
+                "author": {"familyName": null},
+                "tags": ["example"],
+                "phoneNumber": "+01-123-456-7890"
+            })
+        );
+        let mut patched = source.clone();
+        patched.apply_merge_patch(&patch);
+        assert_eq!(patched, target);
+    }
+
+    #[test]
+    fn test_create_then_apply_round_trips() {
+        for (original, _, expected) in rfc_examples() {
+            let mut value = original.clone();
+            value.apply_merge_patch(&create_merge_patch(&original, &expected));
+            assert_eq!(value, expected);
+        }
+        assert_eq!(
+            create_merge_patch(&json!({"a": 1}), &json!({"a": 1})),
+            json!({})
+        );
+    }
+}
@@ -0,0 +1,236 @@
+/// How a `\uXXXX` escape encoding an unpaired UTF-16 surrogate is handled.
+///
+/// A high surrogate (`\uD800`-`\uDBFF`) immediately followed by a low surrogate escape
+/// (`\uDC00`-`\uDFFF`) is always combined into a single character; this policy only applies
+/// to surrogates that are left without a partner.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SurrogatePolicy {
+    /// Reject the string with an error.
+    #[default]
+    Error,
+    /// Replace the lone surrogate with U+FFFD REPLACEMENT CHARACTER.
+    Replace,
+    /// Keep the escape as the six characters of text `\ud800`, so the code unit can still be
+    /// read from the string.
+    ///
+    /// This text cannot be told apart from the input `"\\ud800"`, an escaped backslash followed
+    /// by `ud800`, and is written back that way: the lone surrogate does not survive a round trip
+    /// through serialization.
+    Preserve,
+}
+
+/// How numbers are represented after parsing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberMode {
+    /// Integers become `Number::Int` or `Number::UInt`, everything else a `Number::Float`.
+    #[default]
+    Native,
+    /// Every number becomes a `Number::Decimal` holding its exact source text, so no precision
+    /// is lost and values such as `1e400` are accepted.
+    Exact,
+}
+
+/// How an object member whose key already appeared earlier in the same object is handled.
+///
+/// RFC 8259 leaves the meaning of duplicate keys open, and parsers disagree on which value
+/// wins, so payloads relying on them can be read differently by different systems.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeyPolicy {
+    /// Reject the object with an error pointing at both occurrences.
+    Error,
+    /// Keep the value of the first occurrence and ignore the later ones.
+    FirstWins,
+    /// Keep the value of the last occurrence, at the position of the first one.
+    #[default]
+    LastWins,
+    /// Keep every occurrence as a separate member, see [`Map::get_all`](crate::Map::get_all).
+    KeepAll,
+}
+
+/// Options controlling how JSON text is parsed.
+#[derive(Debug, Clone)]
+pub struct ParseOptions {
+    pub lone_surrogates: SurrogatePolicy,
+    pub number_mode: NumberMode,
+    pub duplicate_keys: DuplicateKeyPolicy,
+    /// How many arrays and objects may be nested inside each other, 128 by default. Deeper
+    /// input fails with [`ErrorKind::NestingTooDeep`](crate::ErrorKind::NestingTooDeep)
+    /// instead of exhausting the stack.
+    pub max_depth: usize,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            lone_surrogates: SurrogatePolicy::default(),
+            number_mode: NumberMode::default(),
+            duplicate_keys: DuplicateKeyPolicy::default(),
+            max_depth: 128,
+        }
+    }
+}
+
+/// The whitespace written for each level of nesting when pretty printing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Indent {
+    Spaces(usize),
+    /// One tab per level, counted as eight columns against [`WriteOptions::max_width`].
+    Tabs,
+}
+
+/// Which characters are written as escapes in strings, beyond the quotation marks, backslashes
+/// and control characters that JSON always requires to be escaped.
+///
+/// The flags combine, e.g. `Escaping { non_ascii: true, ..Escaping::HTML_SAFE }`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Escaping {
+    /// Writes every character outside printable ASCII as `\uXXXX`, characters beyond the Basic
+    /// Multilingual Plane as a surrogate pair such as `\ud83d\ude00`.
+    pub non_ascii: bool,
+    /// Escapes `<`, `>` and `&`, so the text can sit inside an HTML `<script>` element.
+    pub html: bool,
+    /// Escapes U+2028 LINE SEPARATOR and U+2029 PARAGRAPH SEPARATOR, which end a string
+    /// literal in JavaScript engines older than ES2019.
+    pub line_separators: bool,
+}
+
+impl Escaping {
+    /// Only what JSON requires.
+    pub const MINIMAL: Escaping = Escaping {
+        non_ascii: false,
+        html: false,
+        line_separators: false,
+    };
+    /// Pure ASCII output, for transports that cannot carry anything else.
+    pub const ASCII: Escaping = Escaping {
+        non_ascii: true,
+        ..Escaping::MINIMAL
+    };
+    /// Safe to embed as a JavaScript literal.
+    pub const JS_SAFE: Escaping = Escaping {
+        line_separators: true,
+        ..Escaping::MINIMAL
+    };
+    /// Safe to embed in an HTML `<script>` element.
+    pub const HTML_SAFE: Escaping = Escaping {
+        html: true,
+        line_separators: true,
+        ..Escaping::MINIMAL
+    };
+}
+
+/// The order in which object members are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum KeyOrder {
+    /// The order of the document, as parsed or inserted.
+    #[default]
+    Document,
+    /// Sorted by key, comparing code points.
+    Sorted,
+    /// Keys that are array indexes, such as `"0"` or `"42"` but not `"01"`, first in numeric
+    /// order, then the others in document order. This is the order of JavaScript objects.
+    IndexesFirst,
+}
+
+/// How numbers are written.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberFormat {
+    /// Integers and decimals as they are, floats as the shortest text that reads back as the
+    /// same float, e.g. `1.0` or `1e21`. Non-finite floats are written as `null`.
+    #[default]
+    Json,
+    /// Like Python's `json.dumps`: floats as `repr` writes them, e.g. `1e+16` or `1e-05`, and
+    /// non-finite floats as `NaN`, `Infinity` and `-Infinity`, which Python reads back.
+    Python,
+    /// Like JavaScript's `JSON.stringify`: every number as the nearest double, e.g. `5` for
+    /// `5.0` and `1e+21`, and non-finite values as `null`.
+    JavaScript,
+    /// Like Go's `encoding/json`: integers as they are, floats like JavaScript does except that
+    /// negative zero stays `-0`. Non-finite floats, which Go refuses, are written as `null`.
+    Go,
+}
+
+/// Options controlling how values are written as JSON text.
+///
+/// The default writes compact JSON, like `Display` does. [`WriteOptions::pretty`] starts from
+/// indented output instead.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WriteOptions {
+    /// Breaks arrays and objects over several lines, indented this way. `None` writes the
+    /// whole value on one line.
+    pub indent: Option<Indent>,
+    pub key_order: KeyOrder,
+    /// When indenting, arrays and objects that fit on one line within this many columns,
+    /// counting the indentation, key and trailing separator, stay on one line.
+    pub max_width: Option<usize>,
+    /// Written between array elements and between object members. Trailing whitespace is left
+    /// out before a line break.
+    pub item_separator: String,
+    /// Written between an object key and its value.
+    pub key_separator: String,
+    pub escaping: Escaping,
+    pub number_format: NumberFormat,
+    /// Colours keys, strings, numbers and literals with ANSI escape codes, for terminals.
+    pub color: bool,
+}
+
+impl Default for WriteOptions {
+    fn default() -> Self {
+        WriteOptions {
+            indent: None,
+            key_order: KeyOrder::Document,
+            max_width: None,
+            item_separator: ",".to_string(),
+            key_separator: ":".to_string(),
+            escaping: Escaping::MINIMAL,
+            number_format: NumberFormat::Json,
+            color: false,
+        }
+    }
+}
+
+impl WriteOptions {
+    /// Two-space indentation with every element on its own line, and `", "` and `": "` as
+    /// separators.
+    pub fn pretty() -> Self {
+        WriteOptions {
+            indent: Some(Indent::Spaces(2)),
+            item_separator: ", ".to_string(),
+            key_separator: ": ".to_string(),
+            ..WriteOptions::default()
+        }
+    }
+
+    /// The output of Python's `json.dumps` with its default arguments: `", "` and `": "` as
+    /// separators, ASCII only, and floats as Python writes them.
+    pub fn python() -> Self {
+        WriteOptions {
+            item_separator: ", ".to_string(),
+            key_separator: ": ".to_string(),
+            escaping: Escaping::ASCII,
+            number_format: NumberFormat::Python,
+            ..WriteOptions::default()
+        }
+    }
+
+    /// The output of JavaScript's `JSON.stringify`: compact, with members in the order a
+    /// JavaScript object keeps them and numbers as JavaScript writes them.
+    pub fn javascript() -> Self {
+        WriteOptions {
+            key_order: KeyOrder::IndexesFirst,
+            number_format: NumberFormat::JavaScript,
+            ..WriteOptions::default()
+        }
+    }
+
+    /// The output of Go's `json.Marshal` for maps: compact, sorted keys, HTML-safe escaping
+    /// and numbers as Go writes them.
+    pub fn go() -> Self {
+        WriteOptions {
+            key_order: KeyOrder::Sorted,
+            escaping: Escaping::HTML_SAFE,
+            number_format: NumberFormat::Go,
+            ..WriteOptions::default()
+        }
+    }
+}
@@ -1,100 +1,533 @@
-use std::{collections::HashMap, iter::Peekable, slice::Iter};
+use std::{
+    cell::{Cell, OnceCell, RefCell},
+    collections::HashMap,
+    iter::Peekable,
+    slice::Iter,
+};
 
 use crate::{
-    tokenizer::Token,
-    types::{JsonDocument, JsonValue},
+    error::{ErrorKind, LineIndex, ParseError},
+    map::Map,
+    options::{DuplicateKeyPolicy, ParseOptions},
+    tokenizer::{Span, Token},
+    types::JsonValue,
 };
 
+type TokenIter<'t> = Peekable<Iter<'t, (Token, Span)>>;
+
 #[derive(Debug)]
-pub struct Parser {
-    pub tokens: Vec<Token>,
+pub struct Parser<'a> {
+    pub input_string: &'a str,
+    pub tokens: Vec<(Token, Span)>,
+    pub options: ParseOptions,
+    /// Record errors in `errors` and resynchronise instead of stopping at the first one.
+    pub recover: bool,
+    pub errors: RefCell<Vec<ParseError>>,
+    lines: OnceCell<LineIndex<'a>>,
+    /// Number of arrays and objects open at the current token.
+    depth: Cell<usize>,
 }
 
-impl Parser {
-    pub fn parse_tokens(&self) -> Result<JsonDocument, String> {
+impl<'a> Parser<'a> {
+    #[cfg(test)]
+    pub fn new(input_string: &'a str, tokens: Vec<(Token, Span)>) -> Self {
+        Self::with_options(input_string, tokens, ParseOptions::default())
+    }
+
+    pub fn with_options(
+        input_string: &'a str,
+        tokens: Vec<(Token, Span)>,
+        options: ParseOptions,
+    ) -> Self {
+        Parser {
+            input_string,
+            tokens,
+            options,
+            recover: false,
+            errors: RefCell::new(Vec::new()),
+            lines: OnceCell::new(),
+            depth: Cell::new(0),
+        }
+    }
+
+    pub fn parse_tokens(&self) -> Result<JsonValue, ParseError> {
         let mut token_iter = self.tokens.iter().peekable();
 
+        let value = self.parse_value(&mut token_iter)?;
+
+        // nothing but whitespace may follow the root value
         match token_iter.next() {
-            Some(Token::LeftBrace) => self.parse_object(&mut token_iter),
-            Some(Token::LeftBracket) => self.parse_array(&mut token_iter),
-            _ => Err("Invalid JSON".to_string()),
+            None | Some((Token::Invalid, _)) => {}
+            Some((_, span)) => self.report(
+                self.error(ErrorKind::TrailingData, span.clone())
+                    .hinting("only one root value is allowed"),
+            )?,
         }
+
+        Ok(value)
     }
 
-    fn parse_value(&self, token_iter: &mut Peekable<Iter<Token>>) -> Result<JsonValue, String> {
-        match token_iter.next() {
-            Some(Token::LeftBrace) => {
-                let obj = self.parse_object(token_iter)?;
-                return Ok(JsonValue::Document(Box::new(obj)));
+    fn error(&self, kind: ErrorKind, span: Span) -> ParseError {
+        ParseError::new(kind, span, self.lines())
+    }
+
+    /// Line starts of the input, only computed once the first error is reported.
+    fn lines(&self) -> &LineIndex<'a> {
+        self.lines.get_or_init(|| LineIndex::new(self.input_string))
+    }
+
+    /// Fails with `error`, or records it and lets the caller resynchronise when recovering.
+    fn report(&self, error: ParseError) -> Result<(), ParseError> {
+        if !self.recover {
+            return Err(error);
+        }
+
+        self.errors.borrow_mut().push(error);
+        Ok(())
+    }
+
+    /// Reports the token that was found where one of `expected` should have been.
+    ///
+    /// Invalid tokens are skipped silently, the tokenizer has already reported them.
+    fn report_unexpected(
+        &self,
+        found: Option<&(Token, Span)>,
+        expected: &[&'static str],
+        hint: Option<&'static str>,
+    ) -> Result<(), ParseError> {
+        let error = match found {
+            Some((Token::Invalid, _)) => return Ok(()),
+            Some((_, span)) => self.error(ErrorKind::UnexpectedToken, span.clone()),
+            None => {
+                let end = self.input_string.len();
+                self.error(ErrorKind::UnexpectedEof, end..end)
+            }
+        }
+        .expecting(expected);
+
+        self.report(match hint {
+            Some(hint) => error.hinting(hint),
+            None => error,
+        })
+    }
+
+    /// Rejects a closing token that directly follows a comma, consuming it when recovering.
+    fn check_trailing_comma(
+        &self,
+        token_iter: &mut TokenIter,
+        closing: &Token,
+    ) -> Result<bool, ParseError> {
+        match token_iter.peek() {
+            Some((token, span)) if token == closing => {
+                self.report(
+                    self.error(ErrorKind::UnexpectedToken, span.clone())
+                        .expecting(&["value"])
+                        .hinting("trailing comma not allowed"),
+                )?;
+                token_iter.next();
+                Ok(true)
             }
-            Some(Token::LeftBracket) => {
-                let arr = self.parse_array(token_iter)?;
-                return Ok(JsonValue::Document(Box::new(arr)));
+            _ => Ok(false),
+        }
+    }
+
+    /// Skips tokens up to the next ',' or closing bracket of the current container.
+    fn skip_to_sync(&self, token_iter: &mut TokenIter) {
+        let mut depth = 0;
+
+        while let Some((token, _)) = token_iter.peek() {
+            match token {
+                Token::LeftBrace | Token::LeftBracket => depth += 1,
+                Token::RightBrace | Token::RightBracket | Token::Comma if depth == 0 => return,
+                Token::RightBrace | Token::RightBracket => depth -= 1,
+                _ => {}
+            }
+            token_iter.next();
+        }
+    }
+
+    fn parse_value(&self, token_iter: &mut TokenIter) -> Result<JsonValue, ParseError> {
+        match token_iter.peek().copied() {
+            Some((Token::LeftBrace | Token::LeftBracket, span))
+                if self.depth.get() >= self.options.max_depth =>
+            {
+                self.report(
+                    self.error(ErrorKind::NestingTooDeep, span.clone())
+                        .hinting("the limit is set by ParseOptions::max_depth"),
+                )?;
+                // the whole container is skipped, including its closing bracket
+                self.skip_to_sync(token_iter);
+                Ok(JsonValue::Null)
             }
-            Some(Token::Null) => {
-                return Ok(JsonValue::Null);
+            Some((Token::LeftBrace, _)) => {
+                token_iter.next();
+                self.depth.set(self.depth.get() + 1);
+                let object = self.parse_object(token_iter)?;
+                self.depth.set(self.depth.get() - 1);
+                Ok(JsonValue::Object(object))
             }
-            Some(Token::Number(n)) => {
-                return Ok(JsonValue::Number(n.clone()));
+            Some((Token::LeftBracket, _)) => {
+                token_iter.next();
+                self.depth.set(self.depth.get() + 1);
+                let array = self.parse_array(token_iter)?;
+                self.depth.set(self.depth.get() - 1);
+                Ok(JsonValue::Array(array))
             }
-            Some(Token::String(s)) => {
-                return Ok(JsonValue::String(s.clone()));
+            Some((token, _))
+                if !matches!(
+                    token,
+                    Token::RightBrace | Token::RightBracket | Token::Colon | Token::Comma
+                ) =>
+            {
+                token_iter.next();
+                Ok(match token {
+                    Token::Number(n) => JsonValue::Number(n.clone()),
+                    Token::String(s) => JsonValue::String(s.clone()),
+                    Token::Boolean(b) => JsonValue::Boolean(*b),
+                    // an invalid token stands in for a value the tokenizer could not read
+                    _ => JsonValue::Null,
+                })
             }
-            Some(Token::Boolean(b)) => {
-                return Ok(JsonValue::Boolean(b.clone()));
+            other => {
+                self.report_unexpected(other, &["value"], None)?;
+                self.skip_to_sync(token_iter);
+                Ok(JsonValue::Null)
             }
-            _ => Err("Unexpected token".to_string()),
         }
     }
 
-    fn parse_object(&self, token_iter: &mut Peekable<Iter<Token>>) -> Result<JsonDocument, String> {
-        let mut object: HashMap<String, crate::types::JsonValue> = std::collections::HashMap::new();
+    fn parse_object(&self, token_iter: &mut TokenIter) -> Result<Map, ParseError> {
+        let mut object = Map::new();
+        // where each key first appeared, to point at both occurrences of a duplicate
+        let mut key_spans: HashMap<&String, &Span> = HashMap::new();
+
+        // an empty object closes immediately, otherwise every comma must be followed by a key
+        if let Some((Token::RightBrace, _)) = token_iter.peek() {
+            token_iter.next();
+            return Ok(object);
+        }
 
         loop {
-            match token_iter.next() {
-                Some(Token::RightBrace) => return Ok(JsonDocument::Object(object)),
-                Some(Token::String(key)) => {
-                    if let Some(Token::Colon) = token_iter.next() {
-                        let value = self.parse_value(token_iter)?;
-                        object.insert(key.clone(), value);
-
-                        match token_iter.next() {
-                            Some(Token::Comma) => continue,
-                            Some(Token::RightBrace) => return Ok(JsonDocument::Object(object)),
-                            _ => return Err("Unexpected token".to_string()),
+            let mut key = None;
+            let mut value = None;
+
+            match token_iter.peek().copied() {
+                Some((Token::String(s), span)) => {
+                    token_iter.next();
+                    key = Some((s, span));
+                }
+                // an unquoted or malformed key has already been reported by the tokenizer
+                Some((Token::Invalid, _)) => {
+                    token_iter.next();
+                }
+                None => {
+                    self.report_unexpected(None, &["string"], None)?;
+                    return Ok(object);
+                }
+                other => {
+                    self.report_unexpected(
+                        other,
+                        &["string"],
+                        Some("object keys must be strings in double quotes"),
+                    )?;
+                    self.skip_to_sync(token_iter);
+                }
+            }
+
+            if key.is_some() || matches!(token_iter.peek(), Some((Token::Colon, _))) {
+                match token_iter.peek().copied() {
+                    Some((Token::Colon, _)) => {
+                        token_iter.next();
+                        value = Some(self.parse_value(token_iter)?);
+                    }
+                    other => {
+                        self.report_unexpected(
+                            other,
+                            &["':'"],
+                            Some("object keys must be followed by ':'"),
+                        )?;
+                        // keep the value of a member that is only missing its ':'
+                        if let Some((
+                            Token::String(_) | Token::Number(_) | Token::Boolean(_) | Token::Null,
+                            _,
+                        )) = other
+                        {
+                            value = Some(self.parse_value(token_iter)?);
                         }
-                    } else {
-                        return Err("Unexpected token".to_string());
                     }
                 }
-                _ => return Err("Unexpected token".to_string()),
+            }
+
+            if let (Some((key, span)), Some(value)) = (key, value) {
+                self.insert_member(&mut object, &mut key_spans, key, span, value)?;
+            }
+
+            // after a member, expect ',' or '}'
+            loop {
+                match token_iter.peek().copied() {
+                    Some((Token::Comma, _)) => {
+                        token_iter.next();
+                        if self.check_trailing_comma(token_iter, &Token::RightBrace)? {
+                            return Ok(object);
+                        }
+                        break;
+                    }
+                    Some((Token::RightBrace, _)) => {
+                        token_iter.next();
+                        return Ok(object);
+                    }
+                    other @ (Some((Token::RightBracket, _)) | None) => {
+                        self.report_unexpected(other, &["','", "'}'"], None)?;
+                        return Ok(object);
+                    }
+                    other @ Some((Token::String(_), _)) => {
+                        self.report_unexpected(
+                            other,
+                            &["','", "'}'"],
+                            Some("missing ',' between object members"),
+                        )?;
+                        break;
+                    }
+                    other => {
+                        self.report_unexpected(other, &["','", "'}'"], None)?;
+                        self.skip_to_sync(token_iter);
+                    }
+                }
             }
         }
     }
 
-    fn parse_array(&self, token_iter: &mut Peekable<Iter<Token>>) -> Result<JsonDocument, String> {
+    /// Adds a member to `object`, applying the duplicate key policy if `key` is already present.
+    fn insert_member<'t>(
+        &self,
+        object: &mut Map,
+        key_spans: &mut HashMap<&'t String, &'t Span>,
+        key: &'t String,
+        span: &'t Span,
+        value: JsonValue,
+    ) -> Result<(), ParseError> {
+        if !object.contains_key(key) {
+            if self.options.duplicate_keys == DuplicateKeyPolicy::Error {
+                key_spans.insert(key, span);
+            }
+            object.insert(key.clone(), value);
+            return Ok(());
+        }
+
+        match self.options.duplicate_keys {
+            DuplicateKeyPolicy::Error => {
+                let first = key_spans
+                    .get(key)
+                    .map_or(span.clone(), |&first| first.clone());
+                // when recovering, the first value is kept
+                self.report(
+                    self.error(ErrorKind::DuplicateKey, span.clone())
+                        .relating(first, self.lines())
+                        .hinting("each key may only appear once in an object"),
+                )?;
+            }
+            DuplicateKeyPolicy::FirstWins => {}
+            DuplicateKeyPolicy::LastWins => {
+                object.insert(key.clone(), value);
+            }
+            DuplicateKeyPolicy::KeepAll => object.append(key.clone(), value),
+        }
+
+        Ok(())
+    }
+
+    fn parse_array(&self, token_iter: &mut TokenIter) -> Result<Vec<JsonValue>, ParseError> {
         let mut arr: Vec<JsonValue> = Vec::new();
 
+        // an empty array closes immediately, otherwise every comma must be followed by a value
+        if let Some((Token::RightBracket, _)) = token_iter.peek() {
+            token_iter.next();
+            return Ok(arr);
+        }
+
         loop {
-            match token_iter.peek() {
-                Some(Token::RightBracket) => {
+            let value = self.parse_value(token_iter)?;
+            arr.push(value);
+
+            // after a value, expect ',' or ']'
+            match token_iter.peek().copied() {
+                Some((Token::Comma, _)) => {
                     token_iter.next();
-                    return Ok(JsonDocument::Array(arr));
-                }
-                Some(_) => {
-                    let value = self.parse_value(token_iter)?;
-                    arr.push(value);
-
-                    match token_iter.next() {
-                        Some(Token::Comma) => continue,
-                        Some(Token::RightBracket) => return Ok(JsonDocument::Array(arr)),
-                        _ => return Err("Unexpected token".to_string()),
+                    if self.check_trailing_comma(token_iter, &Token::RightBracket)? {
+                        return Ok(arr);
                     }
                 }
-                None => {
-                    return Err("Unexpected end of array".to_string());
+                Some((Token::RightBracket, _)) => {
+                    token_iter.next();
+                    return Ok(arr);
+                }
+                other @ (Some((Token::RightBrace, _)) | None) => {
+                    self.report_unexpected(other, &["','", "']'"], None)?;
+                    return Ok(arr);
+                }
+                other @ Some((Token::Colon, _)) => {
+                    self.report_unexpected(other, &["','", "']'"], None)?;
+                    token_iter.next();
+                }
+                // anything else starts the next value, so only the ',' is missing
+                other => {
+                    self.report_unexpected(
+                        other,
+                        &["','", "']'"],
+                        Some("missing ',' between array elements"),
+                    )?;
                 }
             }
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{tokenizer::Tokenizer, types::Number};
+
+    fn parse(input: &str) -> Result<JsonValue, ParseError> {
+        let parser = Parser::new(input, Tokenizer::new(input).tokenize_json()?);
+
+        parser.parse_tokens()
+    }
+
+    fn parse_object_with(
+        input: &str,
+        duplicate_keys: DuplicateKeyPolicy,
+    ) -> Result<Map, ParseError> {
+        let options = ParseOptions {
+            duplicate_keys,
+            ..ParseOptions::default()
+        };
+        let parser = Parser::with_options(input, Tokenizer::new(input).tokenize_json()?, options);
+
+        match parser.parse_tokens()? {
+            JsonValue::Object(object) => Ok(object),
+            other => panic!("Expect an object, got {:?}", other),
+        }
+    }
+
+    fn int(i: i64) -> JsonValue {
+        JsonValue::Number(Number::Int(i))
+    }
+
+    #[test]
+    fn test_parse_tokens_with_scalar_root() {
+        match parse("42") {
+            Ok(result) => assert_eq!(result, JsonValue::Number(Number::Int(42))),
+            Err(e) => panic!("should not throw this error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_parse_tokens_with_trailing_data() {
+        match parse("[]]") {
+            Ok(_) => panic!("Expect error for trailing data"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::TrailingData);
+                assert_eq!(err.offset(), 2);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_tokens_with_empty_input() {
+        match parse("  ") {
+            Ok(_) => panic!("Expect error for empty input"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::UnexpectedEof),
+        }
+    }
+
+    #[test]
+    fn test_parse_object_with_missing_comma() {
+        match parse("{\"a\": 1\n \"b\": 2}") {
+            Ok(_) => panic!("Expect error for missing comma"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+                assert_eq!(err.expected(), &["','", "'}'"]);
+                assert_eq!((err.line(), err.column()), (2, 2));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_array_with_trailing_comma() {
+        match parse("[1, 2,]") {
+            Ok(_) => panic!("Expect error for trailing comma"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::UnexpectedToken);
+                assert_eq!(err.span(), 6..7);
+                assert_eq!(err.expected(), &["value"]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_array_unclosed() {
+        match parse("[1, 2") {
+            Ok(_) => panic!("Expect error for unclosed array"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::UnexpectedEof);
+                assert_eq!(err.offset(), 5);
+                assert_eq!(err.expected(), &["','", "']'"]);
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_object_duplicate_key_policies() {
+        let input = r#"{"a": 1, "b": 2, "a": 3}"#;
+
+        let last = parse_object_with(input, DuplicateKeyPolicy::LastWins).unwrap();
+        assert_eq!(
+            last.iter().collect::<Vec<_>>(),
+            vec![(&"a".to_string(), &int(3)), (&"b".to_string(), &int(2))]
+        );
+
+        let first = parse_object_with(input, DuplicateKeyPolicy::FirstWins).unwrap();
+        assert_eq!(first.get("a"), Some(&int(1)));
+        assert_eq!(first.len(), 2);
+
+        let all = parse_object_with(input, DuplicateKeyPolicy::KeepAll).unwrap();
+        assert_eq!(all.len(), 3);
+        assert_eq!(all.get("a"), Some(&int(1)));
+        assert_eq!(all.get_all("a").collect::<Vec<_>>(), vec![&int(1), &int(3)]);
+    }
+
+    #[test]
+    fn test_parse_object_duplicate_key_error_points_at_both() {
+        let input = "{\"a\": 1,\n \"b\": {\"a\": 2},\n \"a\": 3}";
+
+        match parse_object_with(input, DuplicateKeyPolicy::Error) {
+            Ok(_) => panic!("Expect error for duplicate key"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::DuplicateKey);
+                assert_eq!(err.span(), 26..29);
+                assert_eq!(err.related_span(), Some(1..4));
+                assert_eq!((err.line(), err.column()), (3, 2));
+                assert_eq!(err.related_position(), Some((1, 2)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_nesting_limit() {
+        let nested = |depth: usize| format!("{}{}", "[".repeat(depth), "]".repeat(depth));
+        let max_depth = ParseOptions::default().max_depth;
+
+        assert!(parse(&nested(max_depth)).is_ok());
+        match parse(&nested(max_depth + 1)) {
+            Ok(_) => panic!("Expect error for deep nesting"),
+            Err(err) => {
+                assert_eq!(err.kind(), ErrorKind::NestingTooDeep);
+                assert_eq!(err.offset(), max_depth);
+            }
+        }
+        match parse(&"[{\"a\": ".repeat(100_000)) {
+            Ok(_) => panic!("Expect error for deep nesting"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::NestingTooDeep),
+        }
+    }
+}
@@ -0,0 +1,702 @@
+use std::{error::Error, fmt};
+
+use crate::{
+    diff::diff,
+    map::Map,
+    pointer::{
+        array_index, insertion_index, missing_key, not_a_container, JsonPointer, PointerError,
+    },
+    types::JsonValue,
+};
+
+/// One operation of a JSON Patch (RFC 6902).
+#[derive(Debug, Clone, PartialEq)]
+pub enum PatchOperation {
+    /// Sets an object member, inserts into an array (`-` appends) or replaces the whole value.
+    Add {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    Remove {
+        path: JsonPointer,
+    },
+    /// Replaces a value that must already exist.
+    Replace {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+    /// Removes the value at `from` and adds it at `path`.
+    Move {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    Copy {
+        from: JsonPointer,
+        path: JsonPointer,
+    },
+    /// Fails the patch unless the value at `path` equals `value`. Numbers compare by value and
+    /// object members in any order.
+    Test {
+        path: JsonPointer,
+        value: JsonValue,
+    },
+}
+
+/// A JSON Patch: operations applied in order, all or nothing.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Patch {
+    operations: Vec<PatchOperation>,
+}
+
+/// Why a patch could not be read or applied.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PatchErrorKind {
+    /// The patch document is not an array of well-formed operation objects.
+    InvalidPatch,
+    /// A `path` or `from` does not lead to a value, or to a place where one can be added.
+    PathNotFound,
+    /// A `test` operation found a different value.
+    TestFailed,
+    /// Removing the whole value, or moving a value into one of its own children.
+    InvalidTarget,
+}
+
+/// Error returned when a patch cannot be read or applied, with the position of the operation
+/// that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatchError {
+    kind: PatchErrorKind,
+    operation: Option<usize>,
+    message: String,
+    pointer: Option<PointerError>,
+}
+
+impl PatchError {
+    fn new(kind: PatchErrorKind, message: impl Into<String>) -> Self {
+        PatchError {
+            kind,
+            operation: None,
+            message: message.into(),
+            pointer: None,
+        }
+    }
+
+    fn invalid(message: impl Into<String>) -> Self {
+        PatchError::new(PatchErrorKind::InvalidPatch, message)
+    }
+
+    fn pointer(kind: PatchErrorKind, error: PointerError) -> Self {
+        PatchError {
+            pointer: Some(error.clone()),
+            ..PatchError::new(kind, error.to_string())
+        }
+    }
+
+    fn not_found(error: PointerError) -> Self {
+        PatchError::pointer(PatchErrorKind::PathNotFound, error)
+    }
+
+    fn at_operation(self, operation: usize) -> Self {
+        PatchError {
+            operation: Some(operation),
+            ..self
+        }
+    }
+
+    pub fn kind(&self) -> PatchErrorKind {
+        self.kind
+    }
+
+    /// Position in the patch of the operation that failed, starting at 0. `None` when the patch
+    /// document as a whole is invalid.
+    pub fn operation(&self) -> Option<usize> {
+        self.operation
+    }
+
+    /// The underlying pointer error, when a `path` or `from` is invalid or does not resolve.
+    pub fn pointer_error(&self) -> Option<&PointerError> {
+        self.pointer.as_ref()
+    }
+}
+
+impl fmt::Display for PatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.operation {
+            Some(operation) => write!(f, "operation {} failed: {}", operation, self.message),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Error for PatchError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.pointer.as_ref().map(|error| error as _)
+    }
+}
+
+impl Patch {
+    pub fn new() -> Self {
+        Patch::default()
+    }
+
+    pub fn operations(&self) -> &[PatchOperation] {
+        &self.operations
+    }
+
+    pub fn push(&mut self, operation: PatchOperation) {
+        self.operations.push(operation);
+    }
+
+    /// Reads a patch document: an array of operation objects such as
+    /// `{"op": "add", "path": "/a", "value": 1}`. Unknown members are ignored.
+    pub fn from_json(document: &JsonValue) -> Result<Patch, PatchError> {
+        let operations = document
+            .as_array()
+            .ok_or_else(|| PatchError::invalid("a patch must be an array of operations"))?;
+
+        let operations = operations
+            .iter()
+            .enumerate()
+            .map(|(i, operation)| {
+                PatchOperation::from_json(operation).map_err(|error| error.at_operation(i))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Patch { operations })
+    }
+
+    /// Generates the patch that turns `from` into `to`, comparing arrays element by element.
+    ///
+    /// For other ways of pairing array elements, use
+    /// [`diff_with`](crate::diff_with) and [`Diff::to_patch`](crate::Diff::to_patch).
+    pub fn between(from: &JsonValue, to: &JsonValue) -> Patch {
+        diff(from, to).to_patch()
+    }
+
+    /// The patch as a JSON Patch document.
+    pub fn to_json(&self) -> JsonValue {
+        self.operations
+            .iter()
+            .map(PatchOperation::to_json)
+            .collect()
+    }
+}
+
+impl From<Vec<PatchOperation>> for Patch {
+    fn from(operations: Vec<PatchOperation>) -> Self {
+        Patch { operations }
+    }
+}
+
+impl FromIterator<PatchOperation> for Patch {
+    fn from_iter<I: IntoIterator<Item = PatchOperation>>(iter: I) -> Self {
+        Patch {
+            operations: iter.into_iter().collect(),
+        }
+    }
+}
+
+impl PatchOperation {
+    /// The `op` member of the operation, e.g. `"add"`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            PatchOperation::Add { .. } => "add",
+            PatchOperation::Remove { .. } => "remove",
+            PatchOperation::Replace { .. } => "replace",
+            PatchOperation::Move { .. } => "move",
+            PatchOperation::Copy { .. } => "copy",
+            PatchOperation::Test { .. } => "test",
+        }
+    }
+
+    fn from_json(operation: &JsonValue) -> Result<PatchOperation, PatchError> {
+        let obj = operation
+            .as_object()
+            .ok_or_else(|| PatchError::invalid("an operation must be an object"))?;
+
+        let member = |name: &str| {
+            obj.get(name)
+                .ok_or_else(|| PatchError::invalid(format!("missing {:?} member", name)))
+        };
+        let pointer = |name: &str| {
+            let text = member(name)?
+                .as_str()
+                .ok_or_else(|| PatchError::invalid(format!("{:?} must be a string", name)))?;
+            JsonPointer::parse(text)
+                .map_err(|error| PatchError::pointer(PatchErrorKind::InvalidPatch, error))
+        };
+        let value = || member("value").cloned();
+
+        let op = member("op")?
+            .as_str()
+            .ok_or_else(|| PatchError::invalid("\"op\" must be a string"))?;
+        let operation = match op {
+            "add" => PatchOperation::Add {
+                path: pointer("path")?,
+                value: value()?,
+            },
+            "remove" => PatchOperation::Remove {
+                path: pointer("path")?,
+            },
+            "replace" => PatchOperation::Replace {
+                path: pointer("path")?,
+                value: value()?,
+            },
+            "move" => PatchOperation::Move {
+                from: pointer("from")?,
+                path: pointer("path")?,
+            },
+            "copy" => PatchOperation::Copy {
+                from: pointer("from")?,
+                path: pointer("path")?,
+            },
+            "test" => PatchOperation::Test {
+                path: pointer("path")?,
+                value: value()?,
+            },
+            other => return Err(PatchError::invalid(format!("unknown op {:?}", other))),
+        };
+
+        Ok(operation)
+    }
+
+    pub fn to_json(&self) -> JsonValue {
+        let mut obj = Map::new();
+        obj.insert("op", JsonValue::from(self.name()));
+
+        let (from, path, value) = match self {
+            PatchOperation::Add { path, value }
+            | PatchOperation::Replace { path, value }
+            | PatchOperation::Test { path, value } => (None, path, Some(value)),
+            PatchOperation::Remove { path } => (None, path, None),
+            PatchOperation::Move { from, path } | PatchOperation::Copy { from, path } => {
+                (Some(from), path, None)
+            }
+        };
+        if let Some(from) = from {
+            obj.insert("from", JsonValue::String(from.to_string()));
+        }
+        obj.insert("path", JsonValue::String(path.to_string()));
+        if let Some(value) = value {
+            obj.insert("value", value.clone());
+        }
+
+        JsonValue::Object(obj)
+    }
+
+    fn apply(&self, target: &mut JsonValue) -> Result<(), PatchError> {
+        match self {
+            PatchOperation::Add { path, value } => add(target, path, value.clone()),
+            PatchOperation::Remove { path } => remove(target, path).map(drop),
+            PatchOperation::Replace { path, value } => {
+                let depth = path.tokens().len();
+                *target
+                    .resolve_mut(path, depth)
+                    .map_err(PatchError::not_found)? = value.clone();
+                Ok(())
+            }
+            PatchOperation::Move { from, path } => {
+                if path == from {
+                    return target
+                        .resolve(from)
+                        .map(drop)
+                        .map_err(PatchError::not_found);
+                }
+                if path.starts_with(from) {
+                    return Err(PatchError::new(
+                        PatchErrorKind::InvalidTarget,
+                        format!(
+                            "cannot move {:?} into its own child {:?}",
+                            from.to_string(),
+                            path.to_string()
+                        ),
+                    ));
+                }
+                let value = remove(target, from)?;
+                add(target, path, value)
+            }
+            PatchOperation::Copy { from, path } => {
+                let value = target.resolve(from).map_err(PatchError::not_found)?;
+                add(target, path, value.clone())
+            }
+            PatchOperation::Test { path, value } => {
+                let found = target.resolve(path).map_err(PatchError::not_found)?;
+                match found.value_eq(value) {
+                    true => Ok(()),
+                    false => Err(PatchError::new(
+                        PatchErrorKind::TestFailed,
+                        format!(
+                            "the value at {:?} is not the expected one",
+                            path.to_string()
+                        ),
+                    )),
+                }
+            }
+        }
+    }
+}
+
+fn add(target: &mut JsonValue, path: &JsonPointer, value: JsonValue) -> Result<(), PatchError> {
+    let last = match path.tokens().split_last() {
+        Some((last, _)) => last,
+        None => {
+            *target = value;
+            return Ok(());
+        }
+    };
+    let depth = path.tokens().len() - 1;
+    let failed = |error| PatchError::not_found(path.token_error(depth, error));
+
+    let parent = target
+        .resolve_mut(path, depth)
+        .map_err(PatchError::not_found)?;
+    let type_name = parent.type_name();
+    match parent {
+        JsonValue::Object(obj) => {
+            obj.insert(last.as_str(), value);
+        }
+        JsonValue::Array(arr) => {
+            let index = insertion_index(last, arr.len()).map_err(failed)?;
+            arr.insert(index, value);
+        }
+        _ => return Err(failed(not_a_container(type_name))),
+    }
+
+    Ok(())
+}
+
+fn remove(target: &mut JsonValue, path: &JsonPointer) -> Result<JsonValue, PatchError> {
+    let last = match path.tokens().split_last() {
+        Some((last, _)) => last,
+        None => {
+            return Err(PatchError::new(
+                PatchErrorKind::InvalidTarget,
+                "the whole value cannot be removed",
+            ))
+        }
+    };
+    let depth = path.tokens().len() - 1;
+    let failed = |error| PatchError::not_found(path.token_error(depth, error));
+
+    let parent = target
+        .resolve_mut(path, depth)
+        .map_err(PatchError::not_found)?;
+    let type_name = parent.type_name();
+    match parent {
+        JsonValue::Object(obj) => obj.remove(last).ok_or_else(|| failed(missing_key())),
+        JsonValue::Array(arr) => {
+            let index = array_index(last, arr.len()).map_err(failed)?;
+            Ok(arr.remove(index))
+        }
+        _ => Err(failed(not_a_container(type_name))),
+    }
+}
+
+impl JsonValue {
+    /// Applies the operations of `patch` in order.
+    ///
+    /// If an operation fails, the value is left as it was before the patch and the error gives
+    /// the position of the failing operation.
+    pub fn apply_patch(&mut self, patch: &Patch) -> Result<(), PatchError> {
+        let mut patched = self.clone();
+
+        for (i, operation) in patch.operations.iter().enumerate() {
+            operation
+                .apply(&mut patched)
+                .map_err(|error| error.at_operation(i))?;
+        }
+
+        *self = patched;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{json, parse_json, parse_json_with, NumberMode, ParseOptions};
+
+    fn patch(operations: JsonValue) -> Patch {
+        Patch::from_json(&operations).unwrap()
+    }
+
+    fn patched(mut doc: JsonValue, operations: JsonValue) -> Result<JsonValue, PatchError> {
+        doc.apply_patch(&patch(operations))?;
+        Ok(doc)
+    }
+
+    #[test]
+    fn test_rfc_examples() {
+        let cases = [
+            (
+                json!({"foo": "bar"}),
+                json!([{"op": "add", "path": "/baz", "value": "qux"}]),
+                json!({"baz": "qux", "foo": "bar"}),
+            ),
+            (
+                json!({"foo": ["bar", "baz"]}),
+                json!([{"op": "add", "path": "/foo/1", "value": "qux"}]),
+                json!({"foo": ["bar", "qux", "baz"]}),
+            ),
+            (
+                json!({"baz": "qux", "foo": "bar"}),
+                json!([{"op": "remove", "path": "/baz"}]),
+                json!({"foo": "bar"}),
+            ),
+            (
+                json!({"foo": ["bar", "qux", "baz"]}),
+                json!([{"op": "remove", "path": "/foo/1"}]),
+                json!({"foo": ["bar", "baz"]}),
+            ),
+            (
+                json!({"baz": "qux", "foo": "bar"}),
+                json!([{"op": "replace", "path": "/baz", "value": "boo"}]),
+                json!({"baz": "boo", "foo": "bar"}),
+            ),
+            (
+                json!({"foo": {"bar": "baz", "waldo": "fred"}, "qux": {"corge": "grault"}}),
+                json!([{"op": "move", "from": "/foo/waldo", "path": "/qux/thud"}]),
+                json!({"foo": {"bar": "baz"}, "qux": {"corge": "grault", "thud": "fred"}}),
+            ),
+            (
+                json!({"foo": ["all", "grass", "cows", "eat"]}),
+                json!([{"op": "move", "from": "/foo/1", "path": "/foo/3"}]),
+                json!({"foo": ["all", "cows", "eat", "grass"]}),
+            ),
+            (
+                json!({"foo": "bar"}),
+                json!([{"op": "add", "path": "/child", "value": {"grandchild": {}}}]),
+                json!({"foo": "bar", "child": {"grandchild": {}}}),
+            ),
+            (
+                json!({"foo": ["bar"]}),
+                json!([{"op": "add", "path": "/foo/-", "value": ["abc", "def"]}]),
+                json!({"foo": ["bar", ["abc", "def"]]}),
+            ),
+            (
+                json!({"/": 9, "~1": 10}),
+                json!([{"op": "test", "path": "/~01", "value": 10}]),
+                json!({"/": 9, "~1": 10}),
+            ),
+            (
+                json!({"a": [1, {"b": 2.0}]}),
+                json!([
+                    {"op": "test", "path": "/a", "value": [1.0, {"b": 2}]},
+                    {"op": "copy", "from": "/a/1", "path": "/c"},
+                    {"op": "add", "path": "", "value": {"root": true}}
+                ]),
+                json!({"root": true}),
+            ),
+        ];
+
+        for (doc, operations, expected) in cases {
+            assert_eq!(
+                patched(doc, operations.clone()),
+                Ok(expected),
+                "{:?}",
+                operations
+            );
+        }
+    }
+
+    #[test]
+    fn test_failed_patch_leaves_value_unchanged() {
+        let mut doc = json!({"foo": "bar", "list": [1]});
+        let operations = patch(json!([
+            {"op": "add", "path": "/baz", "value": 1},
+            {"op": "remove", "path": "/list/0"},
+            {"op": "test", "path": "/foo", "value": "other"}
+        ]));
+
+        let error = doc.apply_patch(&operations).unwrap_err();
+
+        assert_eq!(error.kind(), PatchErrorKind::TestFailed);
+        assert_eq!(error.operation(), Some(2));
+        assert_eq!(
+            error.to_string(),
+            "operation 2 failed: the value at \"/foo\" is not the expected one"
+        );
+        assert_eq!(doc, json!({"foo": "bar", "list": [1]}));
+    }
+
+    #[test]
+    fn test_apply_errors() {
+        let doc = json!({"foo": "bar", "list": [1, 2], "n": 1});
+        let failure = |operation: JsonValue| patched(doc.clone(), json!([operation])).unwrap_err();
+
+        let error = failure(json!({"op": "add", "path": "/missing/a", "value": 1}));
+        assert_eq!(error.kind(), PatchErrorKind::PathNotFound);
+        assert_eq!(
+            error.to_string(),
+            "operation 0 failed: invalid pointer \"/missing/a\": segment 0 (\"missing\") is not a key of the object"
+        );
+        assert_eq!(error.pointer_error().unwrap().segment(), 0);
+
+        let cases = [
+            (
+                json!({"op": "add", "path": "/list/3", "value": 1}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "add", "path": "/list/01", "value": 1}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "add", "path": "/n/a", "value": 1}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "remove", "path": "/list/2"}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "remove", "path": "/list/-"}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "remove", "path": ""}),
+                PatchErrorKind::InvalidTarget,
+            ),
+            (
+                json!({"op": "replace", "path": "/baz", "value": 1}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "move", "from": "/list", "path": "/list/0"}),
+                PatchErrorKind::InvalidTarget,
+            ),
+            (
+                json!({"op": "copy", "from": "/baz", "path": "/qux"}),
+                PatchErrorKind::PathNotFound,
+            ),
+            (
+                json!({"op": "test", "path": "/n", "value": "1"}),
+                PatchErrorKind::TestFailed,
+            ),
+        ];
+        for (operation, kind) in cases {
+            assert_eq!(failure(operation.clone()).kind(), kind, "{:?}", operation);
+        }
+    }
+
+    #[test]
+    fn test_exact_numbers() {
+        let exact = |text: &str| {
+            let options = ParseOptions {
+                number_mode: NumberMode::Exact,
+                ..ParseOptions::default()
+            };
+            parse_json_with(text, &options).unwrap()
+        };
+        let test = |value: &str, expected: &str| {
+            let operations = exact(&format!(
+                r#"[{{"op": "test", "path": "/p", "value": {}}}]"#,
+                expected
+            ));
+            exact(&format!(r#"{{"p": {}}}"#, value)).apply_patch(&patch(operations))
+        };
+
+        let failed = |result: Result<(), PatchError>| result.unwrap_err().kind();
+        assert_eq!(
+            failed(test("12345678901234567890123", "12345678901234567890124")),
+            PatchErrorKind::TestFailed
+        );
+        assert_eq!(
+            failed(test("0.1000000000000000055511151231257827", "0.1")),
+            PatchErrorKind::TestFailed
+        );
+        assert!(test("12345678901234567890123", "1.2345678901234567890123e22").is_ok());
+        assert!(test("100", "1e2").is_ok());
+
+        // a native float compares as its shortest text
+        let mut native = json!({"p": 0.1});
+        assert!(native
+            .apply_patch(&patch(exact(
+                r#"[{"op": "test", "path": "/p", "value": 0.10}]"#
+            )))
+            .is_ok());
+        assert_eq!(
+            failed(native.apply_patch(&patch(exact(
+                r#"[{"op": "test", "path": "/p", "value": 0.1000000000000000055511151231257827}]"#
+            )))),
+            PatchErrorKind::TestFailed
+        );
+    }
+
+    #[test]
+    fn test_invalid_patch_documents() {
+        let cases = [
+            (json!({"op": "add"}), None),
+            (json!([{"path": "/a"}]), Some(0)),
+            (
+                json!([{"op": "remove", "path": "/a"}, {"op": "add", "path": "/a"}]),
+                Some(1),
+            ),
+            (json!([{"op": "copy", "path": "/a"}]), Some(0)),
+            (json!([{"op": "remove", "path": "a"}]), Some(0)),
+            (json!([{"op": "remove", "path": 1}]), Some(0)),
+            (json!([{"op": "frobnicate", "path": "/a"}]), Some(0)),
+            (json!(["add"]), Some(0)),
+        ];
+
+        for (document, operation) in cases {
+            let error = Patch::from_json(&document).unwrap_err();
+            assert_eq!(error.kind(), PatchErrorKind::InvalidPatch, "{:?}", document);
+            assert_eq!(error.operation(), operation, "{:?}", document);
+        }
+    }
+
+    #[test]
+    fn test_patch_json_round_trip() {
+        let document = parse_json(
+            r#"[
+                {"op": "add", "path": "/a~1b", "value": [1]},
+                {"op": "remove", "path": "/c"},
+                {"op": "replace", "path": "", "value": null},
+                {"op": "move", "from": "/d", "path": "/e"},
+                {"op": "copy", "from": "/f", "path": "/g/-"},
+                {"op": "test", "path": "/h", "value": {"x": true}}
+            ]"#,
+        )
+        .unwrap();
+        let patch = Patch::from_json(&document).unwrap();
+
+        assert_eq!(patch.operations().len(), 6);
+        assert_eq!(patch.to_json(), document);
+        assert_eq!(
+            patch.operations()[3],
+            PatchOperation::Move {
+                from: JsonPointer::parse("/d").unwrap(),
+                path: JsonPointer::parse("/e").unwrap(),
+            }
+        );
+    }
+
+    #[test]
+    fn test_between() {
+        let from = json!({"name": "api", "replicas": 2, "tags": ["a", "b"], "debug": true});
+        let to = json!({"name": "api", "replicas": 3, "tags": ["a", "c", "d"], "region": "eu"});
+        let patch = Patch::between(&from, &to);
+
+        assert_eq!(
+            patch.to_json(),
+            json!([
+                {"op": "replace", "path": "/replicas", "value": 3},
+                {"op": "replace", "path": "/tags/1", "value": "c"},
+                {"op": "add", "path": "/tags/2", "value": "d"},
+                {"op": "remove", "path": "/debug"},
+                {"op": "add", "path": "/region", "value": "eu"}
+            ])
+        );
+
+        let mut patched = from.clone();
+        patched.apply_patch(&patch).unwrap();
+        assert_eq!(patched, to);
+
+        assert!(Patch::between(&to, &to).operations().is_empty());
+        assert_eq!(
+            Patch::between(&json!(1), &json!("x")).to_json(),
+            json!([{"op": "replace", "path": "", "value": "x"}])
+        );
+    }
+}
@@ -0,0 +1,328 @@
+use std::{error::Error, fmt};
+
+use crate::{map::Map, types::JsonValue};
+
+/// One step of a path into a document: an object key or an array position.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum PathSegment {
+    Key(String),
+    Index(usize),
+}
+
+impl From<&str> for PathSegment {
+    fn from(key: &str) -> Self {
+        PathSegment::Key(key.to_string())
+    }
+}
+
+impl From<String> for PathSegment {
+    fn from(key: String) -> Self {
+        PathSegment::Key(key)
+    }
+}
+
+impl From<usize> for PathSegment {
+    fn from(index: usize) -> Self {
+        PathSegment::Index(index)
+    }
+}
+
+impl fmt::Display for PathSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathSegment::Key(key) => write!(f, "{:?}", key),
+            PathSegment::Index(index) => write!(f, "{}", index),
+        }
+    }
+}
+
+/// Why a path could not be followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathErrorKind {
+    /// A key segment reached a value that is not an object.
+    NotAnObject,
+    /// A position segment reached a value that is not an array.
+    NotAnArray,
+    /// A position past the end of an array. Only the position right after the last element can
+    /// be created.
+    IndexOutOfBounds,
+}
+
+/// Error returned when a path cannot be followed, with the position of the segment that failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    kind: PathErrorKind,
+    segment: usize,
+    found: &'static str,
+}
+
+impl PathError {
+    pub fn kind(&self) -> PathErrorKind {
+        self.kind
+    }
+
+    /// Position in the path of the segment that could not be followed, starting at 0.
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            PathErrorKind::NotAnObject => write!(
+                f,
+                "segment {} expects an object, found {}",
+                self.segment, self.found
+            ),
+            PathErrorKind::NotAnArray => write!(
+                f,
+                "segment {} expects an array, found {}",
+                self.segment, self.found
+            ),
+            PathErrorKind::IndexOutOfBounds => write!(
+                f,
+                "segment {} is out of bounds for {}",
+                self.segment, self.found
+            ),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+impl JsonValue {
+    /// Looks up the value at `path`. `None` if any segment is missing.
+    pub fn get_path(&self, path: &[PathSegment]) -> Option<&JsonValue> {
+        path.iter().try_fold(self, |value, segment| match segment {
+            PathSegment::Key(key) => value.get(key),
+            PathSegment::Index(index) => value.get_index(*index),
+        })
+    }
+
+    pub fn get_path_mut(&mut self, path: &[PathSegment]) -> Option<&mut JsonValue> {
+        path.iter().try_fold(self, |value, segment| match segment {
+            PathSegment::Key(key) => value.get_mut(key),
+            PathSegment::Index(index) => value.get_index_mut(*index),
+        })
+    }
+
+    /// Sets the value at `path`, returning the value it replaced, or `None` if there was no value
+    /// there yet.
+    ///
+    /// Missing intermediate values are created along the way: a missing key or a `Null` becomes
+    /// an object when followed by a key and an array when followed by a position. A position
+    /// may point one past the end of an array to append to it. An empty path replaces the whole
+    /// value. The whole path is checked first, so on error the value is left unchanged.
+    pub fn set_path(
+        &mut self,
+        path: &[PathSegment],
+        value: JsonValue,
+    ) -> Result<Option<JsonValue>, PathError> {
+        let existed = self.check_path(path)?;
+        let mut current = self;
+
+        for (position, segment) in path.iter().enumerate() {
+            let error = |kind, found| PathError {
+                kind,
+                segment: position,
+                found,
+            };
+
+            current = match segment {
+                PathSegment::Key(key) => {
+                    if current.is_null() {
+                        *current = JsonValue::Object(Map::new());
+                    }
+                    let found = current.type_name();
+                    match current.as_object_mut() {
+                        Some(obj) => obj.entry(key.as_str()).or_insert(JsonValue::Null),
+                        None => return Err(error(PathErrorKind::NotAnObject, found)),
+                    }
+                }
+                PathSegment::Index(index) => {
+                    if current.is_null() {
+                        *current = JsonValue::Array(Vec::new());
+                    }
+                    let found = current.type_name();
+                    let arr = match current.as_array_mut() {
+                        Some(arr) => arr,
+                        None => return Err(error(PathErrorKind::NotAnArray, found)),
+                    };
+                    if *index == arr.len() {
+                        arr.push(JsonValue::Null);
+                    }
+                    match arr.get_mut(*index) {
+                        Some(element) => element,
+                        None => return Err(error(PathErrorKind::IndexOutOfBounds, "the array")),
+                    }
+                }
+            };
+        }
+
+        let previous = std::mem::replace(current, value);
+        Ok(existed.then_some(previous))
+    }
+
+    /// Checks that [`JsonValue::set_path`] can follow `path`, without changing anything, and
+    /// tells whether a value is already there.
+    fn check_path(&self, path: &[PathSegment]) -> Result<bool, PathError> {
+        // `None` once the path leaves the existing values, which are then all created
+        let mut current = Some(self);
+
+        for (position, segment) in path.iter().enumerate() {
+            let error = |kind, found| PathError {
+                kind,
+                segment: position,
+                found,
+            };
+
+            current = match (current.filter(|value| !value.is_null()), segment) {
+                (None, PathSegment::Key(_)) => None,
+                // a created array is empty, so only appending works
+                (None, PathSegment::Index(0)) => None,
+                (None, PathSegment::Index(_)) => {
+                    return Err(error(PathErrorKind::IndexOutOfBounds, "the array"))
+                }
+                (Some(value), PathSegment::Key(key)) => match value.as_object() {
+                    Some(obj) => obj.get(key),
+                    None => return Err(error(PathErrorKind::NotAnObject, value.type_name())),
+                },
+                (Some(value), PathSegment::Index(index)) => match value.as_array() {
+                    Some(arr) if *index <= arr.len() => arr.get(*index),
+                    Some(_) => return Err(error(PathErrorKind::IndexOutOfBounds, "the array")),
+                    None => return Err(error(PathErrorKind::NotAnArray, value.type_name())),
+                },
+            };
+        }
+
+        Ok(current.is_some())
+    }
+
+    /// Removes the value at `path` from its parent object or array and returns it. `None` if the
+    /// path does not exist.
+    pub fn remove_path(&mut self, path: &[PathSegment]) -> Option<JsonValue> {
+        let (last, parent) = path.split_last()?;
+
+        match (self.get_path_mut(parent)?, last) {
+            (parent, PathSegment::Key(key)) => parent.remove(key),
+            (parent, PathSegment::Index(index)) => parent.remove_index(*index),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_json, types::Number};
+
+    fn value(input: &str) -> JsonValue {
+        parse_json(input).unwrap()
+    }
+
+    fn int(i: i64) -> JsonValue {
+        JsonValue::Number(Number::Int(i))
+    }
+
+    #[test]
+    fn test_set_path_creates_intermediate_values() {
+        let mut doc = value(r#"{"id": 1}"#);
+
+        let previous = doc.set_path(
+            &["meta".into(), "trace".into(), 0.into()],
+            JsonValue::String("abc".to_string()),
+        );
+
+        assert_eq!(previous, Ok(None));
+        assert_eq!(doc, value(r#"{"id": 1, "meta": {"trace": ["abc"]}}"#));
+    }
+
+    #[test]
+    fn test_set_path_replaces_and_appends() {
+        let mut doc = value(r#"{"list": [1, 2]}"#);
+
+        assert_eq!(
+            doc.set_path(&["list".into(), 0.into()], int(5)),
+            Ok(Some(int(1)))
+        );
+        assert_eq!(doc.set_path(&["list".into(), 2.into()], int(3)), Ok(None));
+        assert_eq!(doc["list"], value("[5, 2, 3]"));
+    }
+
+    #[test]
+    fn test_set_path_errors() {
+        let mut doc = value(r#"{"name": "nail", "list": []}"#);
+
+        let error = doc
+            .set_path(&["name".into(), "first".into()], JsonValue::Null)
+            .unwrap_err();
+        assert_eq!(error.kind(), PathErrorKind::NotAnObject);
+        assert_eq!(error.segment(), 1);
+        assert_eq!(
+            error.to_string(),
+            "segment 1 expects an object, found a string"
+        );
+
+        let error = doc
+            .set_path(&["list".into(), 3.into()], JsonValue::Null)
+            .unwrap_err();
+        assert_eq!(error.kind(), PathErrorKind::IndexOutOfBounds);
+
+        let error = doc.set_path(&[0.into()], JsonValue::Null).unwrap_err();
+        assert_eq!(error.kind(), PathErrorKind::NotAnArray);
+        assert_eq!(error.segment(), 0);
+    }
+
+    #[test]
+    fn test_failed_set_path_leaves_value_unchanged() {
+        let original = value(r#"{"a": 1, "n": null}"#);
+        let mut doc = original.clone();
+
+        for path in [
+            vec!["b".into(), 5.into()],
+            vec!["b".into(), "c".into(), 0.into(), 1.into()],
+            vec!["n".into(), "x".into(), "y".into(), 2.into()],
+        ] {
+            let error = doc.set_path(&path, int(2)).unwrap_err();
+            assert_eq!(error.kind(), PathErrorKind::IndexOutOfBounds);
+            assert_eq!(error.segment(), path.len() - 1);
+            assert_eq!(doc, original);
+        }
+
+        let error = doc.set_path(&["a".into(), "b".into()], int(2)).unwrap_err();
+        assert_eq!(error.kind(), PathErrorKind::NotAnObject);
+        assert_eq!(doc, original);
+    }
+
+    #[test]
+    fn test_set_path_returns_replaced_null() {
+        let mut doc = value(r#"{"n": null}"#);
+
+        assert_eq!(
+            doc.set_path(&["n".into()], int(1)),
+            Ok(Some(JsonValue::Null))
+        );
+        assert_eq!(doc.set_path(&["m".into()], int(1)), Ok(None));
+        assert_eq!(
+            doc.set_path(&[], int(2)),
+            Ok(Some(value(r#"{"n": 1, "m": 1}"#)))
+        );
+    }
+
+    #[test]
+    fn test_remove_path() {
+        let mut doc = value(r#"{"user": {"name": "Ada", "tags": ["a", "b"]}}"#);
+
+        assert_eq!(
+            doc.remove_path(&["user".into(), "tags".into(), 0.into()]),
+            Some(JsonValue::String("a".to_string()))
+        );
+        assert_eq!(
+            doc.remove_path(&["user".into(), "name".into()]),
+            Some(JsonValue::String("Ada".to_string()))
+        );
+        assert_eq!(doc.remove_path(&["user".into(), "missing".into()]), None);
+        assert_eq!(doc.remove_path(&[]), None);
+        assert_eq!(doc, value(r#"{"user": {"tags": ["b"]}}"#));
+    }
+}
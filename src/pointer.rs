@@ -0,0 +1,418 @@
+use std::{error::Error, fmt, str::FromStr};
+
+use crate::types::JsonValue;
+
+/// A parsed JSON Pointer (RFC 6901), such as `/users/0/name`.
+///
+/// Each reference token is kept unescaped: `~1` becomes `/` and `~0` becomes `~`. Formatting the
+/// pointer escapes them again.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct JsonPointer {
+    tokens: Vec<String>,
+}
+
+/// Why a JSON Pointer could not be parsed or resolved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PointerErrorKind {
+    /// A non-empty pointer that does not start with `/`.
+    MissingSlash,
+    /// A `~` not followed by `0` or `1`.
+    InvalidEscape,
+    /// An object does not have the key.
+    MissingKey,
+    /// A token used on an array is neither a number without leading zeros nor `-`.
+    InvalidIndex,
+    /// An array index is past the last element.
+    IndexOutOfBounds,
+    /// `-` was used to look up the element after the last one, which never exists.
+    EndOfArray,
+    /// A token was applied to a value that is neither an object nor an array.
+    NotAContainer,
+}
+
+/// Error returned when a JSON Pointer is malformed or does not resolve, with the segment that
+/// failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PointerError {
+    kind: PointerErrorKind,
+    pointer: String,
+    segment: usize,
+    reason: String,
+}
+
+impl PointerError {
+    pub(crate) fn new(
+        kind: PointerErrorKind,
+        pointer: &str,
+        segment: usize,
+        reason: String,
+    ) -> Self {
+        PointerError {
+            kind,
+            pointer: pointer.to_string(),
+            segment,
+            reason,
+        }
+    }
+
+    pub fn kind(&self) -> PointerErrorKind {
+        self.kind
+    }
+
+    /// The pointer that failed, as it was given.
+    pub fn pointer(&self) -> &str {
+        &self.pointer
+    }
+
+    /// Position of the reference token that failed, starting at 0 for the first one.
+    pub fn segment(&self) -> usize {
+        self.segment
+    }
+}
+
+impl fmt::Display for PointerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid pointer {:?}: {}", self.pointer, self.reason)
+    }
+}
+
+impl Error for PointerError {}
+
+impl JsonPointer {
+    /// The empty pointer, which refers to the whole document.
+    pub fn root() -> Self {
+        JsonPointer::default()
+    }
+
+    /// Parses a pointer in its string form, e.g. `/a~1b/0`.
+    pub fn parse(pointer: &str) -> Result<Self, PointerError> {
+        if pointer.is_empty() {
+            return Ok(JsonPointer::root());
+        }
+        let rest = pointer.strip_prefix('/').ok_or_else(|| {
+            PointerError::new(
+                PointerErrorKind::MissingSlash,
+                pointer,
+                0,
+                "a pointer must be empty or start with '/'".to_string(),
+            )
+        })?;
+
+        let tokens = rest
+            .split('/')
+            .enumerate()
+            .map(|(segment, token)| unescape(token, pointer, segment))
+            .collect::<Result<_, _>>()?;
+
+        Ok(JsonPointer { tokens })
+    }
+
+    /// The unescaped reference tokens.
+    pub fn tokens(&self) -> &[String] {
+        &self.tokens
+    }
+
+    pub fn is_root(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Appends a reference token, given unescaped.
+    pub fn push(&mut self, token: impl Into<String>) {
+        self.tokens.push(token.into());
+    }
+
+    /// The pointer to the value containing this one, or `None` for the root.
+    pub fn parent(&self) -> Option<JsonPointer> {
+        let (_, parent) = self.tokens.split_last()?;
+        Some(JsonPointer {
+            tokens: parent.to_vec(),
+        })
+    }
+
+    /// The last reference token, or `None` for the root.
+    pub fn last(&self) -> Option<&str> {
+        self.tokens.last().map(String::as_str)
+    }
+}
+
+fn unescape(token: &str, pointer: &str, segment: usize) -> Result<String, PointerError> {
+    if !token.contains('~') {
+        return Ok(token.to_string());
+    }
+
+    let mut unescaped = String::with_capacity(token.len());
+    let mut chars = token.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '~' => match chars.next() {
+                Some('0') => unescaped.push('~'),
+                Some('1') => unescaped.push('/'),
+                _ => {
+                    return Err(PointerError::new(
+                        PointerErrorKind::InvalidEscape,
+                        pointer,
+                        segment,
+                        format!(
+                            "segment {} ({:?}) has a '~' not followed by '0' or '1'",
+                            segment, token
+                        ),
+                    ))
+                }
+            },
+            c => unescaped.push(c),
+        }
+    }
+
+    Ok(unescaped)
+}
+
+impl fmt::Display for JsonPointer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for token in &self.tokens {
+            write!(f, "/{}", token.replace('~', "~0").replace('/', "~1"))?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for JsonPointer {
+    type Err = PointerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        JsonPointer::parse(s)
+    }
+}
+
+/// Parses an array index token: `0` or a number without leading zeros.
+fn parse_index(token: &str) -> Option<usize> {
+    let canonical = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+
+    canonical.then(|| token.parse().ok()).flatten()
+}
+
+/// Finds the element that `token` refers to in an array of `len` elements.
+pub(crate) fn array_index(token: &str, len: usize) -> Result<usize, (PointerErrorKind, String)> {
+    if token == "-" {
+        return Err((
+            PointerErrorKind::EndOfArray,
+            format!(
+                "'-' refers to the element after the last one of an array of length {}",
+                len
+            ),
+        ));
+    }
+
+    match parse_index(token) {
+        Some(index) if index < len => Ok(index),
+        Some(_) => Err((
+            PointerErrorKind::IndexOutOfBounds,
+            format!("is out of bounds for an array of length {}", len),
+        )),
+        None => Err((
+            PointerErrorKind::InvalidIndex,
+            "is not a valid array index".to_string(),
+        )),
+    }
+}
+
+/// Finds where `token` inserts into an array of `len` elements: before an existing element, or
+/// at the end for `-` and `len`.
+pub(crate) fn insertion_index(
+    token: &str,
+    len: usize,
+) -> Result<usize, (PointerErrorKind, String)> {
+    match token {
+        "-" => Ok(len),
+        _ => array_index(token, len + 1).map_err(|(kind, _)| match kind {
+            PointerErrorKind::IndexOutOfBounds => (
+                kind,
+                format!("is past the end of an array of length {}", len),
+            ),
+            _ => (kind, "is not a valid array index".to_string()),
+        }),
+    }
+}
+
+impl JsonPointer {
+    /// Whether `prefix` is this pointer or the pointer of one of its ancestors.
+    pub fn starts_with(&self, prefix: &JsonPointer) -> bool {
+        self.tokens.starts_with(&prefix.tokens)
+    }
+
+    /// Builds the error for the reference token at position `segment`.
+    pub(crate) fn token_error(
+        &self,
+        segment: usize,
+        (kind, reason): (PointerErrorKind, String),
+    ) -> PointerError {
+        let reason = format!(
+            "segment {} ({:?}) {}",
+            segment, self.tokens[segment], reason
+        );
+        PointerError::new(kind, &self.to_string(), segment, reason)
+    }
+}
+
+impl JsonValue {
+    /// Looks up the value that a JSON Pointer string such as `/users/0/name` refers to.
+    pub fn pointer(&self, pointer: &str) -> Result<&JsonValue, PointerError> {
+        self.resolve(&JsonPointer::parse(pointer)?)
+    }
+
+    pub fn pointer_mut(&mut self, pointer: &str) -> Result<&mut JsonValue, PointerError> {
+        let pointer = JsonPointer::parse(pointer)?;
+        self.resolve_mut(&pointer, pointer.tokens.len())
+    }
+
+    pub(crate) fn resolve(&self, pointer: &JsonPointer) -> Result<&JsonValue, PointerError> {
+        let mut current = self;
+
+        for (segment, token) in pointer.tokens.iter().enumerate() {
+            let failed = |error| pointer.token_error(segment, error);
+
+            current = match current {
+                JsonValue::Object(obj) => obj.get(token).ok_or_else(|| failed(missing_key()))?,
+                JsonValue::Array(arr) => &arr[array_index(token, arr.len()).map_err(failed)?],
+                other => return Err(failed(not_a_container(other.type_name()))),
+            };
+        }
+
+        Ok(current)
+    }
+
+    /// Follows the first `depth` tokens of `pointer`, e.g. all but the last one to reach the
+    /// parent of the value it refers to.
+    pub(crate) fn resolve_mut(
+        &mut self,
+        pointer: &JsonPointer,
+        depth: usize,
+    ) -> Result<&mut JsonValue, PointerError> {
+        let mut current = self;
+
+        for (segment, token) in pointer.tokens[..depth].iter().enumerate() {
+            let failed = |error| pointer.token_error(segment, error);
+
+            let type_name = current.type_name();
+            current = match current {
+                JsonValue::Object(obj) => {
+                    obj.get_mut(token).ok_or_else(|| failed(missing_key()))?
+                }
+                JsonValue::Array(arr) => {
+                    let index = array_index(token, arr.len()).map_err(failed)?;
+                    &mut arr[index]
+                }
+                _ => return Err(failed(not_a_container(type_name))),
+            };
+        }
+
+        Ok(current)
+    }
+}
+
+pub(crate) fn missing_key() -> (PointerErrorKind, String) {
+    (
+        PointerErrorKind::MissingKey,
+        "is not a key of the object".to_string(),
+    )
+}
+
+pub(crate) fn not_a_container(type_name: &str) -> (PointerErrorKind, String) {
+    (
+        PointerErrorKind::NotAContainer,
+        format!("cannot be looked up in {}", type_name),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    #[test]
+    fn test_pointer_rfc_examples() {
+        let doc = json!({
+            "foo": ["bar", "baz"],
+            "": 0,
+            "a/b": 1,
+            "c%d": 2,
+            "e^f": 3,
+            "g|h": 4,
+            "i\\j": 5,
+            "k\"l": 6,
+            " ": 7,
+            "m~n": 8
+        });
+
+        assert_eq!(doc.pointer(""), Ok(&doc));
+        assert_eq!(doc.pointer("/foo"), Ok(&json!(["bar", "baz"])));
+        assert_eq!(doc.pointer("/foo/0"), Ok(&json!("bar")));
+        for (pointer, expected) in [
+            ("/", 0),
+            ("/a~1b", 1),
+            ("/c%d", 2),
+            ("/e^f", 3),
+            ("/g|h", 4),
+            ("/i\\j", 5),
+            ("/k\"l", 6),
+            ("/ ", 7),
+            ("/m~0n", 8),
+        ] {
+            assert_eq!(doc.pointer(pointer), Ok(&json!(expected)), "{}", pointer);
+        }
+    }
+
+    #[test]
+    fn test_pointer_errors_name_the_segment() {
+        let doc = json!({"users": [{"name": "Ada"}], "count": 1});
+
+        let error = doc.pointer("/users/3/name").unwrap_err();
+        assert_eq!(error.kind(), PointerErrorKind::IndexOutOfBounds);
+        assert_eq!(error.segment(), 1);
+        assert_eq!(
+            error.to_string(),
+            "invalid pointer \"/users/3/name\": segment 1 (\"3\") is out of bounds for an array of length 1"
+        );
+
+        let cases = [
+            ("users", PointerErrorKind::MissingSlash, 0),
+            ("/users/0/age", PointerErrorKind::MissingKey, 2),
+            ("/users/01", PointerErrorKind::InvalidIndex, 1),
+            ("/users/+1", PointerErrorKind::InvalidIndex, 1),
+            ("/users/-", PointerErrorKind::EndOfArray, 1),
+            ("/count/0", PointerErrorKind::NotAContainer, 1),
+            ("/users/~2", PointerErrorKind::InvalidEscape, 1),
+        ];
+        for (pointer, kind, segment) in cases {
+            let error = doc.pointer(pointer).unwrap_err();
+            assert_eq!(
+                (error.kind(), error.segment()),
+                (kind, segment),
+                "{}",
+                pointer
+            );
+        }
+    }
+
+    #[test]
+    fn test_pointer_mut() {
+        let mut doc = json!({"users": [{"name": "Ada"}]});
+
+        *doc.pointer_mut("/users/0/name").unwrap() = json!("Grace");
+        assert_eq!(doc, json!({"users": [{"name": "Grace"}]}));
+        assert!(doc.pointer_mut("/users/1").is_err());
+    }
+
+    #[test]
+    fn test_json_pointer_round_trip() {
+        let pointer = JsonPointer::parse("/a~1b/m~0n/0").unwrap();
+
+        assert_eq!(pointer.tokens(), &["a/b", "m~n", "0"]);
+        assert_eq!(pointer.to_string(), "/a~1b/m~0n/0");
+        assert_eq!(pointer.parent().unwrap().to_string(), "/a~1b/m~0n");
+        assert_eq!(pointer.last(), Some("0"));
+        assert!(JsonPointer::root().parent().is_none());
+        assert_eq!("".parse::<JsonPointer>(), Ok(JsonPointer::root()));
+    }
+}
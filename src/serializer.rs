@@ -0,0 +1,698 @@
+use std::{fmt, io};
+
+#[allow(deprecated)]
+use crate::types::JsonDocument;
+use crate::{
+    float::{write_float_with, FloatLayout},
+    map::Map,
+    options::{Escaping, Indent, KeyOrder, NumberFormat, WriteOptions},
+    types::{JsonValue, Number},
+};
+
+/// ANSI colours used with [`WriteOptions::color`].
+pub(crate) const KEY_COLOR: &str = "\x1b[1;34m";
+const STRING_COLOR: &str = "\x1b[32m";
+const NUMBER_COLOR: &str = "\x1b[36m";
+const LITERAL_COLOR: &str = "\x1b[33m";
+const NULL_COLOR: &str = "\x1b[90m";
+const RESET: &str = "\x1b[0m";
+
+impl JsonValue {
+    /// Writes the value as compact JSON text into any `fmt::Write`, such as a `String`.
+    pub fn write_to<W: fmt::Write + ?Sized>(&self, out: &mut W) -> fmt::Result {
+        self.write_with(out, &WriteOptions::default())
+    }
+
+    /// Same as [`JsonValue::write_to`], with explicit [`WriteOptions`].
+    pub fn write_with<W: fmt::Write + ?Sized>(
+        &self,
+        out: &mut W,
+        options: &WriteOptions,
+    ) -> fmt::Result {
+        Serializer::new(out, options).value(self, 0, 0)
+    }
+
+    /// Writes the value as compact JSON text into any `io::Write`, such as a file or a socket.
+    ///
+    /// Output goes out in many small writes, so unbuffered writers are best wrapped in a
+    /// `BufWriter`.
+    pub fn to_writer<W: io::Write>(&self, writer: W) -> io::Result<()> {
+        self.to_writer_with(writer, &WriteOptions::default())
+    }
+
+    /// Same as [`JsonValue::to_writer`], with explicit [`WriteOptions`].
+    pub fn to_writer_with<W: io::Write>(
+        &self,
+        writer: W,
+        options: &WriteOptions,
+    ) -> io::Result<()> {
+        let mut adapter = IoAdapter {
+            inner: writer,
+            error: None,
+        };
+
+        self.write_with(&mut adapter, options).map_err(|_| {
+            adapter
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))
+        })
+    }
+
+    /// Returns the value as JSON text written with `options`.
+    pub fn to_string_with(&self, options: &WriteOptions) -> String {
+        let mut text = String::new();
+        // writing to a String cannot fail
+        let _ = self.write_with(&mut text, options);
+        text
+    }
+
+    /// Returns the value as indented JSON text, see [`WriteOptions::pretty`].
+    pub fn to_string_pretty(&self) -> String {
+        self.to_string_with(&WriteOptions::pretty())
+    }
+}
+
+/// Writes the value as compact JSON text, without any whitespace, or as indented text with the
+/// alternate flag (`{:#}`).
+///
+/// The text parses back to an equal value with `parse_json`, except for non-finite floats,
+/// which JSON cannot represent and are written as `null`.
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.alternate() {
+            true => self.write_with(f, &WriteOptions::pretty()),
+            false => self.write_to(f),
+        }
+    }
+}
+
+#[allow(deprecated)]
+impl fmt::Display for JsonDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let options = match f.alternate() {
+            true => WriteOptions::pretty(),
+            false => WriteOptions::default(),
+        };
+        let mut serializer = Serializer::new(f, &options);
+
+        match self {
+            JsonDocument::Array(arr) => serializer.array(arr, 0, 0),
+            JsonDocument::Object(obj) => serializer.object(obj, 0, 0),
+        }
+    }
+}
+
+pub(crate) struct Serializer<'a, W: ?Sized> {
+    out: &'a mut W,
+    options: &'a WriteOptions,
+    /// Set while writing a container that fits on one line, so nothing inside it breaks.
+    inline: bool,
+    color: bool,
+}
+
+impl<'a, W: fmt::Write + ?Sized> Serializer<'a, W> {
+    pub(crate) fn new(out: &'a mut W, options: &'a WriteOptions) -> Self {
+        Serializer {
+            out,
+            options,
+            inline: options.indent.is_none(),
+            color: options.color,
+        }
+    }
+
+    /// Writes `value` at nesting level `depth`. `reserved` counts the columns taken on its
+    /// first line before it and after it, which decides whether it fits within `max_width`.
+    pub(crate) fn value(
+        &mut self,
+        value: &JsonValue,
+        depth: usize,
+        reserved: usize,
+    ) -> fmt::Result {
+        match value {
+            JsonValue::Null => self.painted(NULL_COLOR, |out| out.write_str("null")),
+            JsonValue::Boolean(b) => self.painted(LITERAL_COLOR, |out| {
+                out.write_str(if *b { "true" } else { "false" })
+            }),
+            JsonValue::Number(n) => {
+                let format = self.options.number_format;
+                self.painted(NUMBER_COLOR, |out| write_number(out, n, format))
+            }
+            JsonValue::String(s) => self.painted(STRING_COLOR, |out| {
+                write_string(out, s, self.options.escaping)
+            }),
+            JsonValue::Array(arr) => self.array(arr, depth, reserved),
+            JsonValue::Object(obj) => self.object(obj, depth, reserved),
+        }
+    }
+
+    fn array(&mut self, arr: &[JsonValue], depth: usize, reserved: usize) -> fmt::Result {
+        if arr.is_empty() {
+            return self.out.write_str("[]");
+        }
+
+        let broken = self.breaks(reserved, |serializer| serializer.array(arr, 0, 0));
+        let items = arr.iter().map(|element| (None, element));
+        self.container(('[', ']'), items, broken, depth)
+    }
+
+    fn object(&mut self, obj: &Map, depth: usize, reserved: usize) -> fmt::Result {
+        if obj.is_empty() {
+            return self.out.write_str("{}");
+        }
+
+        let broken = self.breaks(reserved, |serializer| serializer.object(obj, 0, 0));
+        let mut members: Vec<_> = obj.iter().collect();
+        match self.options.key_order {
+            KeyOrder::Document => {}
+            KeyOrder::Sorted => members.sort_by_key(|(key, _)| *key),
+            // a stable sort keeps the other keys in document order
+            KeyOrder::IndexesFirst => {
+                members.sort_by_key(|(key, _)| array_index(key).map_or((1, 0), |index| (0, index)))
+            }
+        }
+        let items = members
+            .into_iter()
+            .map(|(key, value)| (Some(key.as_str()), value));
+        self.container(('{', '}'), items, broken, depth)
+    }
+
+    fn container<'v>(
+        &mut self,
+        (open, close): (char, char),
+        items: impl ExactSizeIterator<Item = (Option<&'v str>, &'v JsonValue)>,
+        broken: bool,
+        depth: usize,
+    ) -> fmt::Result {
+        let was_inline = std::mem::replace(&mut self.inline, !broken);
+        let separator = match broken {
+            true => self.options.item_separator.trim_end(),
+            false => self.options.item_separator.as_str(),
+        };
+        let last = items.len() - 1;
+
+        self.out.write_char(open)?;
+        for (i, (key, value)) in items.enumerate() {
+            if i > 0 {
+                self.out.write_str(separator)?;
+            }
+            if broken {
+                self.newline(depth + 1)?;
+            }
+
+            let mut reserved = self.indent_width(depth + 1);
+            if i < last {
+                reserved += separator.chars().count();
+            }
+            if let Some(key) = key {
+                self.painted(KEY_COLOR, |out| {
+                    write_string(out, key, self.options.escaping)
+                })?;
+                self.out.write_str(&self.options.key_separator)?;
+                if broken {
+                    reserved += measure(|out| write_string(out, key, self.options.escaping))
+                        + self.options.key_separator.chars().count();
+                }
+            }
+            self.value(value, depth + 1, reserved)?;
+        }
+        if broken {
+            self.newline(depth)?;
+        }
+        self.out.write_char(close)?;
+
+        self.inline = was_inline;
+        Ok(())
+    }
+
+    /// Whether the container that `write` writes has to be broken over several lines.
+    fn breaks(
+        &self,
+        reserved: usize,
+        write: impl FnOnce(&mut Serializer<'_, Counter>) -> fmt::Result,
+    ) -> bool {
+        if self.inline {
+            return false;
+        }
+        let limit = match self.options.max_width {
+            Some(max_width) if max_width >= reserved => max_width - reserved,
+            _ => return true,
+        };
+
+        let mut counter = Counter { columns: 0, limit };
+        let mut serializer = Serializer {
+            out: &mut counter,
+            options: self.options,
+            inline: true,
+            color: false,
+        };
+        write(&mut serializer).is_err()
+    }
+
+    pub(crate) fn newline(&mut self, depth: usize) -> fmt::Result {
+        self.out.write_char('\n')?;
+        for _ in 0..depth {
+            match self.options.indent {
+                Some(Indent::Spaces(width)) => write!(self.out, "{:width$}", "", width = width)?,
+                Some(Indent::Tabs) => self.out.write_char('\t')?,
+                None => {}
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn indent_width(&self, depth: usize) -> usize {
+        match self.options.indent {
+            Some(Indent::Spaces(width)) => depth * width,
+            Some(Indent::Tabs) => depth * 8,
+            None => 0,
+        }
+    }
+
+    /// Runs `write`, wrapped in the colour escape codes when colouring.
+    pub(crate) fn painted(
+        &mut self,
+        color: &str,
+        write: impl FnOnce(&mut W) -> fmt::Result,
+    ) -> fmt::Result {
+        if !self.color {
+            return write(self.out);
+        }
+        self.out.write_str(color)?;
+        write(self.out)?;
+        self.out.write_str(RESET)
+    }
+}
+
+/// Counts the columns that `write` produces.
+pub(crate) fn measure(write: impl FnOnce(&mut Counter) -> fmt::Result) -> usize {
+    let mut counter = Counter {
+        columns: 0,
+        limit: usize::MAX,
+    };
+    let _ = write(&mut counter);
+    counter.columns
+}
+
+/// A `fmt::Write` target that only counts characters, and fails once there are more than
+/// `limit`, so measuring a large value stops early.
+pub(crate) struct Counter {
+    columns: usize,
+    limit: usize,
+}
+
+impl fmt::Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.columns += s.chars().count();
+        match self.columns <= self.limit {
+            true => Ok(()),
+            false => Err(fmt::Error),
+        }
+    }
+}
+
+fn write_number<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    n: &Number,
+    format: NumberFormat,
+) -> fmt::Result {
+    let float = match (format, n) {
+        (NumberFormat::Json, _) => return write!(out, "{}", n),
+        // JavaScript only has doubles
+        (NumberFormat::JavaScript, _) => n.as_f64_lossy(),
+        (_, Number::Float(float)) => *float,
+        _ => return write!(out, "{}", n),
+    };
+
+    match format {
+        NumberFormat::Python if float.is_nan() => out.write_str("NaN"),
+        NumberFormat::Python if float.is_infinite() => match float > 0.0 {
+            true => out.write_str("Infinity"),
+            false => out.write_str("-Infinity"),
+        },
+        _ if !float.is_finite() => out.write_str("null"),
+        NumberFormat::Python => write_float_with(out, float, &FloatLayout::PYTHON),
+        NumberFormat::JavaScript if float == 0.0 => out.write_char('0'),
+        _ => write_float_with(out, float, &FloatLayout::JAVASCRIPT),
+    }
+}
+
+/// The value of a key that is an array index in JavaScript: a canonical integer below
+/// 2^32 - 1.
+fn array_index(key: &str) -> Option<u32> {
+    if key.len() > 1 && key.starts_with('0') || !key.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    key.parse().ok().filter(|&index| index < u32::MAX)
+}
+
+/// Writes `s` as a JSON string literal.
+///
+/// Quotation marks, backslashes and control characters are always escaped, the common control
+/// characters with their short escapes such as `\n` and the others like `\u001f`. `escaping`
+/// adds more characters, written as `\uXXXX`.
+pub(crate) fn write_string<W: fmt::Write + ?Sized>(
+    out: &mut W,
+    s: &str,
+    escaping: Escaping,
+) -> fmt::Result {
+    out.write_char('"')?;
+
+    // unescaped characters are written in runs rather than one by one
+    let mut start = 0;
+    for (i, c) in s.char_indices() {
+        let escape = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\u{08}' => "\\b",
+            '\u{0C}' => "\\f",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            '\u{00}'..='\u{1F}' => "",
+            '<' | '>' | '&' if escaping.html => "",
+            '\u{2028}' | '\u{2029}' if escaping.line_separators => "",
+            '\u{7F}'.. if escaping.non_ascii => "",
+            _ => continue,
+        };
+
+        out.write_str(&s[start..i])?;
+        if escape.is_empty() {
+            for unit in c.encode_utf16(&mut [0; 2]) {
+                write!(out, "\\u{:04x}", unit)?;
+            }
+        } else {
+            out.write_str(escape)?;
+        }
+        start = i + c.len_utf8();
+    }
+    out.write_str(&s[start..])?;
+
+    out.write_char('"')
+}
+
+/// Lets the `fmt::Write` serializer write into an `io::Write`, keeping the I/O error that
+/// `fmt::Error` cannot carry.
+struct IoAdapter<W> {
+    inner: W,
+    error: Option<io::Error>,
+}
+
+impl<W: io::Write> fmt::Write for IoAdapter<W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|error| {
+            self.error = Some(error);
+            fmt::Error
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{json, parse_json};
+
+    #[test]
+    fn test_compact_output() {
+        let value = json!({
+            "name": "nail",
+            "tags": ["json", "parser"],
+            "version": 1,
+            "ratio": 0.5,
+            "nested": {"empty": [], "none": null, "ok": true, "obj": {}}
+        });
+
+        assert_eq!(
+            value.to_string(),
+            r#"{"name":"nail","tags":["json","parser"],"version":1,"ratio":0.5,"nested":{"empty":[],"none":null,"ok":true,"obj":{}}}"#
+        );
+        assert_eq!(json!(null).to_string(), "null");
+        assert_eq!(json!(-3).to_string(), "-3");
+        assert_eq!(json!(2.0).to_string(), "2.0");
+        assert_eq!(json!(1e300).to_string(), "1e300");
+        assert_eq!(JsonValue::from(f64::NAN).to_string(), "null");
+        assert_eq!(
+            JsonValue::from(u64::MAX).to_string(),
+            "18446744073709551615"
+        );
+    }
+
+    #[test]
+    fn test_string_escapes() {
+        let value =
+            json!("quote \" backslash \\ slash / tab \t newline \n nul \u{0} unit \u{1f} é 😀");
+
+        assert_eq!(
+            value.to_string(),
+            r#""quote \" backslash \\ slash / tab \t newline \n nul \u0000 unit \u001f é 😀""#
+        );
+        assert_eq!(json!("\u{08}\u{0C}\r").to_string(), r#""\b\f\r""#);
+        assert_eq!(json!("\u{7f}").to_string(), "\"\u{7f}\"");
+    }
+
+    #[test]
+    fn test_escaping_profiles() {
+        let value = json!({"tag": "</script>&", "text": "é\u{7f}😀\u{2028}\u{2029}"});
+        let with = |escaping| WriteOptions {
+            escaping,
+            ..WriteOptions::default()
+        };
+
+        assert_eq!(
+            value.to_string(),
+            "{\"tag\":\"</script>&\",\"text\":\"é\u{7f}😀\u{2028}\u{2029}\"}"
+        );
+        assert_eq!(
+            value.to_string_with(&with(Escaping::ASCII)),
+            r#"{"tag":"</script>&","text":"\u00e9\u007f\ud83d\ude00\u2028\u2029"}"#
+        );
+        assert_eq!(
+            value.to_string_with(&with(Escaping::JS_SAFE)),
+            "{\"tag\":\"</script>&\",\"text\":\"é\u{7f}😀\\u2028\\u2029\"}"
+        );
+        assert_eq!(
+            value.to_string_with(&with(Escaping::HTML_SAFE)),
+            "{\"tag\":\"\\u003c/script\\u003e\\u0026\",\"text\":\"é\u{7f}😀\\u2028\\u2029\"}"
+        );
+
+        let everything = with(Escaping {
+            non_ascii: true,
+            ..Escaping::HTML_SAFE
+        });
+        assert_eq!(
+            json!({"ключ": "<😀>"}).to_string_with(&everything),
+            r#"{"\u043a\u043b\u044e\u0447":"\u003c\ud83d\ude00\u003e"}"#
+        );
+        assert_eq!(
+            parse_json(&value.to_string_with(&everything)).unwrap(),
+            value
+        );
+    }
+
+    #[test]
+    fn test_python_preset() {
+        let value = json!({
+            "a": 1.0,
+            "b": [1e16, 1e-5, 0.0001, 123.456, -0.0, 12345678901234567890u64],
+            "c": "é<\u{2028}>",
+            "d": [f64::NAN, f64::INFINITY, f64::NEG_INFINITY]
+        });
+
+        // json.dumps(value)
+        assert_eq!(
+            value.to_string_with(&WriteOptions::python()),
+            r#"{"a": 1.0, "b": [1e+16, 1e-05, 0.0001, 123.456, -0.0, 12345678901234567890], "c": "\u00e9<\u2028>", "d": [NaN, Infinity, -Infinity]}"#
+        );
+
+        // json.dumps(value, indent=2)
+        let indented = WriteOptions {
+            indent: Some(Indent::Spaces(2)),
+            ..WriteOptions::python()
+        };
+        assert_eq!(
+            json!({"a": [1, {}], "b": []}).to_string_with(&indented),
+            "{\n  \"a\": [\n    1,\n    {}\n  ],\n  \"b\": []\n}"
+        );
+    }
+
+    #[test]
+    fn test_javascript_preset() {
+        let value = json!({
+            "b": 1,
+            "10": [1e21, 1e-7, 5.0, 0.000001, -0.0, 1.5e300],
+            "a": "é\u{2028}",
+            "1": 12345678901234567890u64,
+            "4294967295": null,
+            "4294967294": null,
+            "01": f64::NAN
+        });
+
+        // JSON.stringify(value)
+        assert_eq!(
+            value.to_string_with(&WriteOptions::javascript()),
+            "{\"1\":12345678901234567000,\"10\":[1e+21,1e-7,5,0.000001,0,1.5e+300],\"4294967294\":null,\"b\":1,\"a\":\"é\u{2028}\",\"4294967295\":null,\"01\":null}"
+        );
+    }
+
+    #[test]
+    fn test_go_preset() {
+        let value = json!({
+            "x": [1e21, 1e-7, 5.0, 0.000001, -0.0, 1e20, 12345678901234567890u64],
+            "b": "a&b\u{2029}",
+            "<tag>": true,
+            "a": {"z": 1, "y": 2}
+        });
+
+        // json.Marshal(value)
+        assert_eq!(
+            value.to_string_with(&WriteOptions::go()),
+            r#"{"\u003ctag\u003e":true,"a":{"y":2,"z":1},"b":"a\u0026b\u2029","x":[1e+21,1e-7,5,0.000001,-0,100000000000000000000,12345678901234567890]}"#
+        );
+    }
+
+    #[test]
+    fn test_round_trips_through_parse_json() {
+        let inputs = [
+            r#"{"a":[1,-0.0,1.5e-7,1e21,12345678901234567890,-9223372036854775808],"b":{"c":"\u0001\"\\"}}"#,
+            r#"["", " ", "😀", 0.1, 100.0, true, false, null]"#,
+            r#"{"duplicate":{"":{"":[[[]]]}}}"#,
+            "\"\\u2028\\u2029\"",
+        ];
+
+        for input in inputs {
+            let value = parse_json(input).unwrap();
+            let text = value.to_string();
+            assert_eq!(parse_json(&text).unwrap(), value, "{}", text);
+            assert_eq!(parse_json(&text).unwrap().to_string(), text);
+        }
+    }
+
+    #[test]
+    fn test_writers() {
+        let value = json!({"k": [1, "two"]});
+
+        let mut bytes = Vec::new();
+        value.to_writer(&mut bytes).unwrap();
+        assert_eq!(bytes, br#"{"k":[1,"two"]}"#);
+
+        let mut text = String::from("value: ");
+        value.write_to(&mut text).unwrap();
+        assert_eq!(text, r#"value: {"k":[1,"two"]}"#);
+
+        assert_eq!(
+            JsonValue::Number(Number::Float(f64::INFINITY)).to_string(),
+            "null"
+        );
+    }
+
+    #[test]
+    fn test_to_writer_reports_io_errors() {
+        struct Full;
+
+        impl io::Write for Full {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::WriteZero, "disk full"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let error = json!([1]).to_writer(Full).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn test_pretty_output() {
+        let value = json!({"a": [1, 2], "b": {}, "c": {"d": null, "e": []}});
+        let expected = "{\n  \"a\": [\n    1,\n    2\n  ],\n  \"b\": {},\n  \"c\": {\n    \"d\": null,\n    \"e\": []\n  }\n}";
+
+        assert_eq!(value.to_string_pretty(), expected);
+        assert_eq!(format!("{:#}", value), expected);
+        assert_eq!(parse_json(expected).unwrap(), value);
+        assert_eq!(json!("scalar").to_string_pretty(), "\"scalar\"");
+    }
+
+    #[test]
+    fn test_max_width_keeps_short_containers_inline() {
+        let value = json!({
+            "short": [1, 2, 3],
+            "long": ["aaaaaaaaaa", "bbbbbbbbbb", "cccccccccc"],
+            "nested": {"x": 1}
+        });
+        let options = WriteOptions {
+            max_width: Some(30),
+            ..WriteOptions::pretty()
+        };
+
+        assert_eq!(
+            value.to_string_with(&options),
+            r#"{
+  "short": [1, 2, 3],
+  "long": [
+    "aaaaaaaaaa",
+    "bbbbbbbbbb",
+    "cccccccccc"
+  ],
+  "nested": {"x": 1}
+}"#
+        );
+
+        let width = |max_width| WriteOptions {
+            max_width: Some(max_width),
+            ..WriteOptions::pretty()
+        };
+        assert_eq!(json!([1, 2]).to_string_with(&width(6)), "[1, 2]");
+        assert_eq!(json!([1, 2]).to_string_with(&width(5)), "[\n  1,\n  2\n]");
+    }
+
+    #[test]
+    fn test_indent_sorting_and_separators() {
+        let value = json!({"b": 1, "a": [true], "c": {"z": 0, "y": 0}});
+        let tabs = WriteOptions {
+            indent: Some(Indent::Tabs),
+            key_order: KeyOrder::Sorted,
+            key_separator: " : ".to_string(),
+            ..WriteOptions::default()
+        };
+        let spaced = WriteOptions {
+            key_order: KeyOrder::Sorted,
+            item_separator: ", ".to_string(),
+            key_separator: ": ".to_string(),
+            ..WriteOptions::default()
+        };
+
+        assert_eq!(
+            value.to_string_with(&tabs),
+            "{\n\t\"a\" : [\n\t\ttrue\n\t],\n\t\"b\" : 1,\n\t\"c\" : {\n\t\t\"y\" : 0,\n\t\t\"z\" : 0\n\t}\n}"
+        );
+        assert_eq!(
+            value.to_string_with(&spaced),
+            r#"{"a": [true], "b": 1, "c": {"y": 0, "z": 0}}"#
+        );
+    }
+
+    #[test]
+    fn test_color() {
+        let options = WriteOptions {
+            color: true,
+            ..WriteOptions::default()
+        };
+
+        assert_eq!(
+            json!({"k": ["s", 1, true, null]}).to_string_with(&options),
+            "{\x1b[1;34m\"k\"\x1b[0m:[\x1b[32m\"s\"\x1b[0m,\x1b[36m1\x1b[0m,\x1b[33mtrue\x1b[0m,\x1b[90mnull\x1b[0m]}"
+        );
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_document_display() {
+        let document = JsonDocument::try_from(json!({"a": [1, 2]})).unwrap();
+        assert_eq!(document.to_string(), r#"{"a":[1,2]}"#);
+        assert_eq!(
+            format!("{:#}", document),
+            "{\n  \"a\": [\n    1,\n    2\n  ]\n}"
+        );
+    }
+}
@@ -1,3 +1,18 @@
+use std::{
+    cell::{OnceCell, RefCell},
+    iter::Peekable,
+    ops::Range,
+    str::CharIndices,
+};
+
+use crate::{
+    decimal::Decimal,
+    error::{ErrorKind, LineIndex, ParseError},
+    float::parse_float,
+    options::{NumberMode, ParseOptions, SurrogatePolicy},
+    types::Number,
+};
+
 #[derive(Debug, PartialEq)]
 pub enum Token {
     LeftBrace,
@@ -10,219 +25,495 @@ pub enum Token {
     Number(Number),
     Boolean(bool),
     Null,
+    /// Placeholder for input that could not be tokenized, only produced when recovering.
+    Invalid,
 }
 
+/// Byte range of a token in the input.
+pub type Span = Range<usize>;
+
 #[derive(Debug)]
 pub struct Tokenizer<'a> {
     pub input_string: &'a str,
+    pub options: ParseOptions,
+    /// Record errors in `errors` and keep going instead of stopping at the first one.
+    pub recover: bool,
+    pub errors: RefCell<Vec<ParseError>>,
+    lines: OnceCell<LineIndex<'a>>,
 }
 
-use crate::types::Number;
-use std::{iter::Peekable, str::Chars};
-
 impl<'a> Tokenizer<'a> {
-    pub fn tokenize_json(&self) -> Result<Vec<Token>, String> {
-        let mut tokens: Vec<Token> = Vec::new();
-        let mut chars = self.input_string.chars().peekable();
+    #[cfg(test)]
+    pub fn new(input_string: &'a str) -> Self {
+        Self::with_options(input_string, ParseOptions::default())
+    }
+
+    pub fn with_options(input_string: &'a str, options: ParseOptions) -> Self {
+        Tokenizer {
+            input_string,
+            options,
+            recover: false,
+            errors: RefCell::new(Vec::new()),
+            lines: OnceCell::new(),
+        }
+    }
+
+    pub fn tokenize_json(&self) -> Result<Vec<(Token, Span)>, ParseError> {
+        let mut tokens: Vec<(Token, Span)> = Vec::new();
+        let mut chars = self.input_string.char_indices().peekable();
 
-        while let Some(c) = chars.peek() {
-            match c {
+        while let Some(&(start, c)) = chars.peek() {
+            let token = match c {
                 '{' => {
-                    tokens.push(Token::LeftBrace);
                     chars.next();
+                    Token::LeftBrace
                 }
                 '}' => {
-                    tokens.push(Token::RightBrace);
                     chars.next();
+                    Token::RightBrace
                 }
                 '[' => {
-                    tokens.push(Token::LeftBracket);
                     chars.next();
+                    Token::LeftBracket
                 }
                 ']' => {
-                    tokens.push(Token::RightBracket);
                     chars.next();
+                    Token::RightBracket
                 }
                 ':' => {
-                    tokens.push(Token::Colon);
                     chars.next();
+                    Token::Colon
                 }
                 ',' => {
-                    tokens.push(Token::Comma);
                     chars.next();
+                    Token::Comma
                 }
-                'n' => {
-                    if self.try_tokenize_null(&mut chars) {
-                        tokens.push(Token::Null);
-                    } else {
-                        return Err("Invalid JSON".into());
-                    }
-                }
-                't' => {
-                    if self.try_tokenize_true(&mut chars) {
-                        tokens.push(Token::Boolean(true));
-                    } else {
-                        return Err("Invalid JSON".into());
-                    }
-                }
-                'f' => {
-                    if self.try_tokenize_false(&mut chars) {
-                        tokens.push(Token::Boolean(false));
-                    } else {
-                        return Err("Invalid JSON".into());
-                    }
-                }
-                '"' => {
-                    match self.try_tokenize_string(&mut chars) {
-                        Ok(result) => {
-                            tokens.push(Token::String(result));
-                        }
-                        Err(err) => return Err(err),
-                    };
-                }
-                '0'..='9' | '-' => {
-                    match self.try_tokenize_number(&mut chars) {
-                        Ok(result) => {
-                            tokens.push(Token::Number(result));
-                        }
-                        Err(err) => return Err(err),
-                    };
-                }
-                ' ' | '\n' | '\r' => {
+                'n' => match self.try_tokenize_null(&mut chars) {
+                    true => Token::Null,
+                    false => self.recover_token(&mut chars, self.invalid_literal(start))?,
+                },
+                't' => match self.try_tokenize_true(&mut chars) {
+                    true => Token::Boolean(true),
+                    false => self.recover_token(&mut chars, self.invalid_literal(start))?,
+                },
+                'f' => match self.try_tokenize_false(&mut chars) {
+                    true => Token::Boolean(false),
+                    false => self.recover_token(&mut chars, self.invalid_literal(start))?,
+                },
+                '"' => match self.try_tokenize_string(&mut chars) {
+                    Ok(result) => Token::String(result),
+                    Err(err) => self.recover_token(&mut chars, err)?,
+                },
+                '0'..='9' | '-' => match self.try_tokenize_number(&mut chars) {
+                    Ok(result) => Token::Number(result),
+                    Err(err) => self.recover_token(&mut chars, err)?,
+                },
+                ' ' | '\t' | '\n' | '\r' => {
                     chars.next();
                     continue;
                 }
-                _ => return Err("Invalid JSON".into()),
+                _ => self.recover_token(&mut chars, self.unexpected_character(start, c))?,
             };
+
+            tokens.push((token, start..self.position(&mut chars)));
         }
 
         Ok(tokens)
     }
 
-    fn match_exact_word(&self, chars: &mut Peekable<Chars>, word: &str) -> bool {
+    fn error(&self, kind: ErrorKind, span: Span) -> ParseError {
+        ParseError::new(kind, span, self.lines())
+    }
+
+    /// Line starts of the input, only computed once the first error is reported.
+    fn lines(&self) -> &LineIndex<'a> {
+        self.lines.get_or_init(|| LineIndex::new(self.input_string))
+    }
+
+    /// Fails with `error`, or records it and lets the caller carry on when recovering.
+    fn report(&self, error: ParseError) -> Result<(), ParseError> {
+        if !self.recover {
+            return Err(error);
+        }
+
+        self.errors.borrow_mut().push(error);
+        Ok(())
+    }
+
+    /// Records a token error and skips the rest of the malformed token, up to the next
+    /// whitespace or structural character, leaving an invalid placeholder token behind.
+    fn recover_token(
+        &self,
+        chars: &mut Peekable<CharIndices>,
+        error: ParseError,
+    ) -> Result<Token, ParseError> {
+        let (kind, offset) = (error.kind(), error.offset());
+        self.report(error)?;
+
+        let resume = match kind {
+            ErrorKind::UnterminatedString => self.input_string.len(),
+            _ => self.input_string[offset..]
+                .find(|c: char| " \t\n\r,:[]{}\"".contains(c))
+                .map_or(self.input_string.len(), |length| offset + length.max(1)),
+        };
+
+        while chars.next_if(|&(offset, _)| offset < resume).is_some() {}
+
+        Ok(Token::Invalid)
+    }
+
+    /// Byte offset of the next character, or the input length once everything is consumed.
+    fn position(&self, chars: &mut Peekable<CharIndices>) -> usize {
+        chars
+            .peek()
+            .map_or(self.input_string.len(), |&(offset, _)| offset)
+    }
+
+    /// Byte offset of the end of the bare word starting at `start`.
+    fn word_end(&self, start: usize) -> usize {
+        self.input_string[start..]
+            .find(|c: char| !c.is_alphanumeric() && c != '_')
+            .map_or(self.input_string.len(), |length| start + length)
+    }
+
+    fn word_hint(&self, word: &str) -> &'static str {
+        match word {
+            "NaN" | "Infinity" => "NaN and Infinity are not valid JSON numbers",
+            _ if ["true", "false", "null"].contains(&word.to_lowercase().as_str()) => {
+                "true, false and null must be written in lowercase"
+            }
+            _ => "strings and object keys must be enclosed in double quotes",
+        }
+    }
+
+    fn invalid_literal(&self, start: usize) -> ParseError {
+        let end = self.word_end(start);
+
+        self.error(ErrorKind::InvalidLiteral, start..end)
+            .expecting(&["'true'", "'false'", "'null'"])
+            .hinting(self.word_hint(&self.input_string[start..end]))
+    }
+
+    fn unexpected_character(&self, start: usize, c: char) -> ParseError {
+        let mut span = start..start + c.len_utf8();
+        let hint = match c {
+            '\'' => "strings must use double quotes",
+            '/' | '#' => "comments are not allowed in JSON",
+            '+' => "numbers cannot start with '+'",
+            '.' => "numbers must have a digit before the decimal point",
+            _ if c.is_alphabetic() || c == '_' => {
+                span.end = self.word_end(start);
+                self.word_hint(&self.input_string[span.clone()])
+            }
+            _ => "expected an object, array, string, number, boolean or null",
+        };
+
+        self.error(ErrorKind::UnexpectedCharacter, span)
+            .expecting(&["value"])
+            .hinting(hint)
+    }
+
+    fn match_exact_word(&self, chars: &mut Peekable<CharIndices>, word: &str) -> bool {
         let length = word.len();
+        // only consume the word when it matches, so a failed match can be skipped as a whole
+        let mut lookahead = chars.clone();
+
+        if lookahead
+            .by_ref()
+            .take(length)
+            .map(|(_, c)| c)
+            .eq(word.chars())
+        {
+            *chars = lookahead;
+            return true;
+        }
 
-        chars.take(length).eq(word.chars())
+        false
     }
 
-    fn try_tokenize_null(&self, chars: &mut Peekable<Chars>) -> bool {
+    fn try_tokenize_null(&self, chars: &mut Peekable<CharIndices>) -> bool {
         self.match_exact_word(chars, "null")
     }
 
-    fn try_tokenize_true(&self, chars: &mut Peekable<Chars>) -> bool {
+    fn try_tokenize_true(&self, chars: &mut Peekable<CharIndices>) -> bool {
         self.match_exact_word(chars, "true")
     }
 
-    fn try_tokenize_false(&self, chars: &mut Peekable<Chars>) -> bool {
+    fn try_tokenize_false(&self, chars: &mut Peekable<CharIndices>) -> bool {
         self.match_exact_word(chars, "false")
     }
 
-    fn try_tokenize_string(&self, chars: &mut Peekable<Chars>) -> Result<String, String> {
+    fn try_tokenize_string(&self, chars: &mut Peekable<CharIndices>) -> Result<String, ParseError> {
         // skip the opening double quote
+        let start = self.position(chars);
         chars.next();
 
         let mut extracted_string = String::new();
 
-        while let Some(next_char) = chars.next() {
+        while let Some((offset, next_char)) = chars.next() {
             match next_char {
                 '"' => return Ok(extracted_string),
                 '\\' => {
                     // Handle escape sequences
                     match chars.next() {
-                        Some('"') => extracted_string.push('"'),
-                        Some('\\') => extracted_string.push('\\'),
-                        Some('/') => extracted_string.push('/'),
-                        Some('b') => extracted_string.push('\u{0008}'), // backspace
-                        Some('f') => extracted_string.push('\u{000C}'), // form feed
-                        Some('n') => extracted_string.push('\n'),
-                        Some('r') => extracted_string.push('\r'),
-                        Some('t') => extracted_string.push('\t'),
-                        Some('u') => {
-                            // Unicode escape sequence \uXXXX
-                            let mut unicode_digits = String::new();
-                            for _ in 0..4 {
-                                match chars.next() {
-                                    Some(c) if c.is_ascii_hexdigit() => unicode_digits.push(c),
-                                    _ => return Err("Invalid unicode escape sequence".into()),
-                                }
+                        Some((_, '"')) => extracted_string.push('"'),
+                        Some((_, '\\')) => extracted_string.push('\\'),
+                        Some((_, '/')) => extracted_string.push('/'),
+                        Some((_, 'b')) => extracted_string.push('\u{0008}'), // backspace
+                        Some((_, 'f')) => extracted_string.push('\u{000C}'), // form feed
+                        Some((_, 'n')) => extracted_string.push('\n'),
+                        Some((_, 'r')) => extracted_string.push('\r'),
+                        Some((_, 't')) => extracted_string.push('\t'),
+                        Some((_, 'u')) => match self.read_unicode_escape(chars) {
+                            Some(code_unit) => self.push_code_unit(
+                                chars,
+                                offset,
+                                code_unit,
+                                &mut extracted_string,
+                            )?,
+                            None => {
+                                let span = offset..self.position(chars);
+                                self.report(
+                                    self.error(ErrorKind::InvalidUnicodeEscape, span).hinting(
+                                        "\\u must be followed by exactly four hexadecimal digits",
+                                    ),
+                                )?;
+                                extracted_string.push(char::REPLACEMENT_CHARACTER);
                             }
-                            match u32::from_str_radix(&unicode_digits, 16) {
-                                Ok(code_point) => {
-                                    match char::from_u32(code_point) {
-                                        Some(unicode_char) => extracted_string.push(unicode_char),
-                                        None => return Err("Invalid unicode code point".into()),
-                                    }
+                        },
+                        Some((_, c)) => {
+                            let span = offset..self.position(chars);
+                            let hint = match c {
+                                '\'' => "single quotes do not need to be escaped",
+                                _ => {
+                                    r#"valid escapes are \", \\, \/, \b, \f, \n, \r, \t and \uXXXX"#
                                 }
-                                Err(_) => return Err("Invalid unicode escape sequence".into()),
-                            }
+                            };
+                            self.report(self.error(ErrorKind::InvalidEscape, span).hinting(hint))?;
+                            extracted_string.push(c);
                         }
-                        Some(_) => return Err("Invalid escape sequence".into()),
-                        None => return Err("EOF reached when parsing escape sequence".into()),
+                        None => return Err(self.unterminated_string(start)),
                     }
                 }
+                // control characters must be escaped inside strings
+                '\u{0000}'..='\u{001F}' => {
+                    let hint = match next_char {
+                        '\n' | '\r' => "strings cannot span multiple lines, use \\n instead",
+                        '\t' => "tabs inside strings must be written as \\t",
+                        _ => "control characters must be escaped as \\u00XX",
+                    };
+                    self.report(
+                        self.error(ErrorKind::ControlCharacter, offset..offset + 1)
+                            .hinting(hint),
+                    )?;
+                    // a raw line break most likely means the closing quote is missing
+                    if let '\n' | '\r' = next_char {
+                        return Ok(extracted_string);
+                    }
+                    extracted_string.push(next_char);
+                }
                 _ => extracted_string.push(next_char),
             }
         }
 
-        Err("EOF reached when parsing string".into())
+        Err(self.unterminated_string(start))
     }
 
-    fn try_tokenize_number(&self, chars: &mut Peekable<Chars>) -> Result<Number, String> {
-        const ERROR_MSG: &str = "Invalid number";
-        let mut extracted_string = String::new();
-        let mut has_dot = false;
-        let mut has_number = false;
+    fn unterminated_string(&self, start: usize) -> ParseError {
+        self.error(
+            ErrorKind::UnterminatedString,
+            start..self.input_string.len(),
+        )
+        .expecting(&["'\"'"])
+        .hinting("add the missing closing '\"'")
+    }
 
-        if chars.peek() == Some(&'-') {
-            extracted_string.push('-');
+    fn read_unicode_escape(&self, chars: &mut Peekable<CharIndices>) -> Option<u16> {
+        // Unicode escape sequence \uXXXX, the \u has already been consumed
+        let mut code_unit: u16 = 0;
+        for _ in 0..4 {
+            let digit = chars.peek().and_then(|&(_, c)| c.to_digit(16))?;
             chars.next();
+            code_unit = code_unit * 16 + digit as u16;
         }
 
-        loop {
-            match chars.peek() {
-                Some('0'..='9') => {
-                    extracted_string.push(chars.next().unwrap());
-                    has_number = true;
-                }
-                Some('.') => {
-                    // number cannot have more than 1 .
-                    if has_dot {
-                        return Err(ERROR_MSG.into());
-                    }
-                    // must have number before .
-                    if !has_number {
-                        return Err(ERROR_MSG.into());
+        Some(code_unit)
+    }
+
+    fn push_code_unit(
+        &self,
+        chars: &mut Peekable<CharIndices>,
+        escape_start: usize,
+        code_unit: u16,
+        extracted_string: &mut String,
+    ) -> Result<(), ParseError> {
+        match code_unit {
+            0xD800..=0xDBFF => {
+                // a high surrogate only forms a character together with an escaped low surrogate
+                let mut lookahead = chars.clone();
+                if matches!(lookahead.next(), Some((_, '\\')))
+                    && matches!(lookahead.next(), Some((_, 'u')))
+                {
+                    if let Some(low @ 0xDC00..=0xDFFF) = self.read_unicode_escape(&mut lookahead) {
+                        *chars = lookahead;
+                        let code_point =
+                            0x10000 + ((code_unit as u32 - 0xD800) << 10) + (low as u32 - 0xDC00);
+                        // both halves are in range, so the combined code point is always valid
+                        extracted_string.push(char::from_u32(code_point).unwrap());
+                        return Ok(());
                     }
-                    extracted_string.push(chars.next().unwrap());
-                    has_dot = true;
                 }
-                Some(',' | '\n' | '\r' | ' ' | '}' | ']') => {
-                    return self.parse_number(&extracted_string, has_dot);
+
+                self.push_lone_surrogate(escape_start, code_unit, extracted_string)
+            }
+            0xDC00..=0xDFFF => self.push_lone_surrogate(escape_start, code_unit, extracted_string),
+            // every other BMP code unit is a valid scalar value
+            _ => {
+                extracted_string.push(char::from_u32(code_unit as u32).unwrap());
+                Ok(())
+            }
+        }
+    }
+
+    fn push_lone_surrogate(
+        &self,
+        escape_start: usize,
+        code_unit: u16,
+        extracted_string: &mut String,
+    ) -> Result<(), ParseError> {
+        match self.options.lone_surrogates {
+            SurrogatePolicy::Error => {
+                // \uXXXX is always 6 bytes long
+                self.report(
+                    self.error(ErrorKind::LoneSurrogate, escape_start..escape_start + 6)
+                        .hinting("a high surrogate escape must be directly followed by a low surrogate escape"),
+                )?;
+                extracted_string.push(char::REPLACEMENT_CHARACTER);
+                Ok(())
+            }
+            SurrogatePolicy::Replace => {
+                extracted_string.push(char::REPLACEMENT_CHARACTER);
+                Ok(())
+            }
+            SurrogatePolicy::Preserve => {
+                extracted_string.push_str(&format!("\\u{:04x}", code_unit));
+                Ok(())
+            }
+        }
+    }
+
+    fn try_tokenize_number(&self, chars: &mut Peekable<CharIndices>) -> Result<Number, ParseError> {
+        let start = self.position(chars);
+        let mut is_float = false;
+
+        if let Some((_, '-')) = chars.peek() {
+            chars.next();
+        }
+
+        // integer part: a single 0, or a non-zero digit followed by any digits
+        match chars.peek() {
+            Some((_, '0')) => {
+                chars.next();
+                // leading zeros are not allowed
+                if let Some((_, '0'..='9')) = chars.peek() {
+                    return Err(self
+                        .invalid_number(chars, start)
+                        .hinting("leading zeros are not allowed"));
                 }
-                Some('-') => return Err(ERROR_MSG.into()),
-                Some(_) => return Err(ERROR_MSG.into()),
-                None => break,
+            }
+            Some((_, '1'..='9')) => {
+                self.take_digits(chars);
+            }
+            _ => {
+                return Err(self
+                    .invalid_number(chars, start)
+                    .expecting(&["digit"])
+                    .hinting("'-' must be followed by a digit"))
             }
         }
 
-        if has_number {
-            self.parse_number(&extracted_string, has_dot)
-        } else {
-            Err(ERROR_MSG.into())
+        // fraction part: . must be followed by at least one digit
+        if let Some((_, '.')) = chars.peek() {
+            chars.next();
+            if self.take_digits(chars) == 0 {
+                return Err(self
+                    .invalid_number(chars, start)
+                    .expecting(&["digit"])
+                    .hinting("a decimal point must be followed by at least one digit"));
+            }
+            is_float = true;
         }
+
+        // exponent part: e or E, optional sign, at least one digit
+        if let Some((_, 'e' | 'E')) = chars.peek() {
+            chars.next();
+            if let Some((_, '+' | '-')) = chars.peek() {
+                chars.next();
+            }
+            if self.take_digits(chars) == 0 {
+                return Err(self
+                    .invalid_number(chars, start)
+                    .expecting(&["digit"])
+                    .hinting("an exponent must contain at least one digit"));
+            }
+            is_float = true;
+        }
+
+        // a number must be followed by whitespace, a structural character or the end of input
+        match chars.peek() {
+            None | Some((_, ',' | ' ' | '\t' | '\n' | '\r' | '}' | ']')) => {
+                let span = start..self.position(chars);
+                let text = &self.input_string[span.clone()];
+                if self.options.number_mode == NumberMode::Exact {
+                    return Ok(Number::Decimal(Decimal::from_lexeme(text)));
+                }
+                parse_number(text, is_float).ok_or_else(|| {
+                    self.error(ErrorKind::NumberOverflow, span)
+                        .hinting("the number is too large to be represented")
+                })
+            }
+            Some(_) => Err(self.invalid_number(chars, start)),
+        }
+    }
+
+    /// Reports an invalid number spanning from its start up to and including the offending character.
+    fn invalid_number(&self, chars: &mut Peekable<CharIndices>, start: usize) -> ParseError {
+        let end = chars
+            .peek()
+            .map_or(self.input_string.len(), |&(offset, c)| {
+                offset + c.len_utf8()
+            });
+
+        self.error(ErrorKind::InvalidNumber, start..end)
+    }
+
+    fn take_digits(&self, chars: &mut Peekable<CharIndices>) -> usize {
+        let mut count = 0;
+
+        while let Some((_, '0'..='9')) = chars.peek() {
+            chars.next();
+            count += 1;
+        }
+
+        count
     }
+}
 
-    fn parse_number(&self, s: &str, is_float: bool) -> Result<Number, String> {
-        if is_float {
-            s.parse::<f64>()
-                .map(Number::Float)
-                .map_err(|_| "Invalid float".into())
-        } else {
-            s.parse::<i32>()
-                .map(Number::Int)
-                .map_err(|_| "Invalid integer".into())
+/// Converts the text of a number that follows the JSON grammar, or `None` if it is out of range.
+pub(crate) fn parse_number(text: &str, is_float: bool) -> Option<Number> {
+    if !is_float {
+        if let Ok(i) = text.parse::<i64>() {
+            return Some(Number::Int(i));
+        }
+        if let Ok(u) = text.parse::<u64>() {
+            return Some(Number::UInt(u));
         }
+        // integers beyond the 64-bit ranges are promoted to the nearest float
     }
+
+    Some(parse_float(text))
+        .filter(|f| f.is_finite())
+        .map(Number::Float)
 }
 
 #[cfg(test)]
@@ -231,12 +522,14 @@ mod tests {
 
     use super::*;
 
+    fn without_spans(tokens: Vec<(Token, Span)>) -> Vec<Token> {
+        tokens.into_iter().map(|(token, _)| token).collect()
+    }
+
     #[test]
     fn test_tokenize_json_simplest() {
         let input = r#"{"foo": "bar"}"#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
+        let tokenizer = Tokenizer::new(input);
 
         match tokenizer.tokenize_json() {
             Ok(result) => {
@@ -248,7 +541,7 @@ mod tests {
                     Token::RightBrace,
                 ];
 
-                assert_eq!(result, expected);
+                assert_eq!(without_spans(result), expected);
             }
             Err(e) => panic!("should not throw this error: {:?}", e),
         }
@@ -257,87 +550,71 @@ mod tests {
     #[test]
     fn test_try_tokenize_null() {
         let input = "null";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_null(&mut chars), true);
+        assert!(tokenizer.try_tokenize_null(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_null_return_false() {
         let input = "none";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_null(&mut chars), false);
+        assert!(!tokenizer.try_tokenize_null(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_true() {
         let input = "true";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_true(&mut chars), true);
+        assert!(tokenizer.try_tokenize_true(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_true_return_false() {
         let input = "turtle";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_true(&mut chars), false);
+        assert!(!tokenizer.try_tokenize_true(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_false_return() {
         let input = "false";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_false(&mut chars), true);
+        assert!(tokenizer.try_tokenize_false(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_false_with_suffix() {
         let input = "false, ";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_false(&mut chars), true);
+        assert!(tokenizer.try_tokenize_false(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_false_return_false() {
         let input = "f";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
-        assert_eq!(tokenizer.try_tokenize_false(&mut chars), false);
+        assert!(!tokenizer.try_tokenize_false(&mut chars));
     }
 
     #[test]
     fn test_try_tokenize_string() {
         let input = r#""Hello World!""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(result) => {
@@ -350,26 +627,22 @@ mod tests {
     #[test]
     fn test_try_tokenize_string_unclosed() {
         let input = r#""Hello World!"#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(_) => {
                 panic!("Expect error returned for unclosed string");
             }
-            Err(e) => assert_eq!(e, "EOF reached when parsing string"),
+            Err(e) => assert_eq!(e.kind(), ErrorKind::UnterminatedString),
         }
     }
 
     #[test]
     fn test_try_tokenize_number_with_integer() {
         let input = "23";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(result) => assert_eq!(result, Number::Int(23)),
@@ -380,10 +653,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_number_with_float() {
         let input = "52.1985";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(result) => assert_eq!(result, Number::Float(52.1985)),
@@ -394,10 +665,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_number_with_negative_integer() {
         let input = "-11";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(result) => assert_eq!(result, Number::Int(-11)),
@@ -408,10 +677,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_number_with_negative_float() {
         let input = "-47.9999999";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(result) => assert_eq!(result, Number::Float(-47.9999999)),
@@ -422,10 +689,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_number_with_negative_float2() {
         let input = "-0.33";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(result) => assert_eq!(result, Number::Float(-0.33)),
@@ -433,41 +698,90 @@ mod tests {
         };
     }
 
+    #[test]
+    fn test_try_tokenize_number_with_64_bit_integers() {
+        let cases = [
+            ("3000000000", Number::Int(3_000_000_000)),
+            ("-9223372036854775808", Number::Int(i64::MIN)),
+            ("9223372036854775807", Number::Int(i64::MAX)),
+            (
+                "9223372036854775808",
+                Number::UInt(9_223_372_036_854_775_808),
+            ),
+            ("18446744073709551615", Number::UInt(u64::MAX)),
+            (
+                "18446744073709551616",
+                Number::Float(18446744073709551616.0),
+            ),
+            (
+                "-9223372036854775809",
+                Number::Float(-9223372036854775809.0),
+            ),
+        ];
+
+        for (input, expected) in cases {
+            let tokenizer = Tokenizer::new(input);
+            let mut chars = input.char_indices().peekable();
+
+            match tokenizer.try_tokenize_number(&mut chars) {
+                Ok(result) => assert_eq!(result, expected, "{}", input),
+                Err(_) => panic!("Expect not to throw error for {}", input),
+            };
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_number_in_exact_mode() {
+        let options = ParseOptions {
+            number_mode: NumberMode::Exact,
+            ..ParseOptions::default()
+        };
+
+        for input in [
+            "0.1000000000000000055511151231257827",
+            "1E400",
+            "-0",
+            "18446744073709551616",
+        ] {
+            let tokenizer = Tokenizer::with_options(input, options.clone());
+            let mut chars = input.char_indices().peekable();
+
+            match tokenizer.try_tokenize_number(&mut chars) {
+                Ok(Number::Decimal(result)) => assert_eq!(result.as_str(), input),
+                other => panic!("Expect a decimal for {}, got {:?}", input, other),
+            };
+        }
+    }
+
     #[test]
     fn test_try_tokenize_number_with_2_dots() {
         let input = "-52.33.3";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(_) => panic!("Expect to throw error"),
-            Err(err) => assert_eq!(err, "Invalid number"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidNumber),
         };
     }
 
     #[test]
     fn test_try_tokenize_number_with_2_minus() {
         let input = "-52-11";
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_number(&mut chars) {
             Ok(_) => panic!("Expect to throw error"),
-            Err(err) => assert_eq!(err, "Invalid number"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidNumber),
         };
     }
 
     #[test]
     fn test_try_tokenize_string_with_escaped_quotes() {
         let input = r#""He said \"Hello World!\"""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(result) => {
@@ -480,10 +794,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_string_with_escaped_backslash() {
         let input = r#""Path: C:\\Users\\test""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(result) => {
@@ -496,10 +808,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_string_with_newline_escape() {
         let input = r#""Line 1\nLine 2""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(result) => {
@@ -512,10 +822,8 @@ mod tests {
     #[test]
     fn test_try_tokenize_string_with_unicode_escape() {
         let input = r#""Unicode: \u0048\u0065\u006C\u006C\u006F""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(result) => {
@@ -528,37 +836,31 @@ mod tests {
     #[test]
     fn test_try_tokenize_string_with_invalid_escape() {
         let input = r#""Invalid \x escape""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(_) => panic!("Expect error for invalid escape sequence"),
-            Err(err) => assert_eq!(err, "Invalid escape sequence"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidEscape),
         }
     }
 
     #[test]
     fn test_try_tokenize_string_with_incomplete_unicode() {
         let input = r#""Unicode: \u00""#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
-        let mut chars = input.chars().peekable();
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
 
         match tokenizer.try_tokenize_string(&mut chars) {
             Ok(_) => panic!("Expect error for incomplete unicode escape"),
-            Err(err) => assert_eq!(err, "Invalid unicode escape sequence"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidUnicodeEscape),
         }
     }
 
     #[test]
     fn test_tokenize_json_with_number_at_end_of_object() {
         let input = r#"{"num":42}"#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
+        let tokenizer = Tokenizer::new(input);
 
         match tokenizer.tokenize_json() {
             Ok(result) => {
@@ -569,7 +871,7 @@ mod tests {
                     Token::Number(Number::Int(42)),
                     Token::RightBrace,
                 ];
-                assert_eq!(result, expected);
+                assert_eq!(without_spans(result), expected);
             }
             Err(e) => panic!("should not throw this error: {:?}", e),
         }
@@ -578,9 +880,7 @@ mod tests {
     #[test]
     fn test_tokenize_json_with_number_at_end_of_array() {
         let input = r#"[1,2,3]"#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
+        let tokenizer = Tokenizer::new(input);
 
         match tokenizer.tokenize_json() {
             Ok(result) => {
@@ -593,18 +893,17 @@ mod tests {
                     Token::Number(Number::Int(3)),
                     Token::RightBracket,
                 ];
-                assert_eq!(result, expected);
+                assert_eq!(without_spans(result), expected);
             }
             Err(e) => panic!("should not throw this error: {:?}", e),
         }
     }
 
     #[test]
+    #[allow(clippy::approx_constant)]
     fn test_tokenize_json_with_float_at_end_of_object() {
         let input = r#"{"pi":3.14159}"#;
-        let tokenizer = Tokenizer {
-            input_string: input,
-        };
+        let tokenizer = Tokenizer::new(input);
 
         match tokenizer.tokenize_json() {
             Ok(result) => {
@@ -615,9 +914,198 @@ mod tests {
                     Token::Number(Number::Float(3.14159)),
                     Token::RightBrace,
                 ];
-                assert_eq!(result, expected);
+                assert_eq!(without_spans(result), expected);
             }
             Err(e) => panic!("should not throw this error: {:?}", e),
         }
     }
+
+    #[test]
+    fn test_try_tokenize_number_with_exponent() {
+        for (input, expected) in [("1e10", 1e10), ("2.5E-3", 2.5e-3), ("-0e+1", -0.0)] {
+            let tokenizer = Tokenizer::new(input);
+            let mut chars = input.char_indices().peekable();
+
+            match tokenizer.try_tokenize_number(&mut chars) {
+                Ok(result) => assert_eq!(result, Number::Float(expected)),
+                Err(_) => panic!("Expect not to throw error for {}", input),
+            };
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_number_with_invalid_forms() {
+        for input in ["01", "-01", "1.", "1.e3", "1e", "1e+", "-", "-.5", "1x"] {
+            let tokenizer = Tokenizer::new(input);
+            let mut chars = input.char_indices().peekable();
+
+            match tokenizer.try_tokenize_number(&mut chars) {
+                Ok(_) => panic!("Expect to throw error for {}", input),
+                Err(err) => assert_eq!(err.kind(), ErrorKind::InvalidNumber),
+            };
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_number_out_of_range() {
+        let input = "1e400";
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
+
+        match tokenizer.try_tokenize_number(&mut chars) {
+            Ok(_) => panic!("Expect to throw error"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::NumberOverflow),
+        };
+    }
+
+    #[test]
+    fn test_try_tokenize_string_with_unescaped_control_character() {
+        let input = "\"tab\there\"";
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
+
+        match tokenizer.try_tokenize_string(&mut chars) {
+            Ok(_) => panic!("Expect error for unescaped control character"),
+            Err(err) => assert_eq!(err.kind(), ErrorKind::ControlCharacter),
+        }
+    }
+
+    #[test]
+    fn test_tokenize_json_with_tab_whitespace() {
+        let input = "[\t1,\t2\t]";
+        let tokenizer = Tokenizer::new(input);
+
+        match tokenizer.tokenize_json() {
+            Ok(result) => {
+                let expected = vec![
+                    Token::LeftBracket,
+                    Token::Number(Number::Int(1)),
+                    Token::Comma,
+                    Token::Number(Number::Int(2)),
+                    Token::RightBracket,
+                ];
+                assert_eq!(without_spans(result), expected);
+            }
+            Err(e) => panic!("should not throw this error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_string_with_surrogate_pair() {
+        let input = r#""emoji: \ud83d\ude00, clef: \uD834\uDD1E""#;
+        let tokenizer = Tokenizer::new(input);
+        let mut chars = input.char_indices().peekable();
+
+        match tokenizer.try_tokenize_string(&mut chars) {
+            Ok(result) => assert_eq!(result, "emoji: \u{1F600}, clef: \u{1D11E}"),
+            Err(e) => panic!("Expect success tokenize string with surrogate pair: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_string_with_lone_surrogate_error() {
+        for input in [r#""\ud83d""#, r#""\ude00""#, r#""\ud83dx""#, r#""\ud83dA""#] {
+            let tokenizer = Tokenizer::new(input);
+            let mut chars = input.char_indices().peekable();
+
+            match tokenizer.try_tokenize_string(&mut chars) {
+                Ok(_) => panic!("Expect error for lone surrogate in {}", input),
+                Err(err) => assert_eq!(err.kind(), ErrorKind::LoneSurrogate),
+            }
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_string_with_lone_surrogate_replace() {
+        let input = r#""a\ud83dA\ude00""#;
+        let options = ParseOptions {
+            lone_surrogates: SurrogatePolicy::Replace,
+            ..ParseOptions::default()
+        };
+        let tokenizer = Tokenizer::with_options(input, options);
+        let mut chars = input.char_indices().peekable();
+
+        match tokenizer.try_tokenize_string(&mut chars) {
+            Ok(result) => assert_eq!(result, "a\u{FFFD}A\u{FFFD}"),
+            Err(e) => panic!("Expect success tokenize string: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_try_tokenize_string_with_lone_surrogate_preserve() {
+        let input = r#""\uD83D \uDE00""#;
+        let options = ParseOptions {
+            lone_surrogates: SurrogatePolicy::Preserve,
+            ..ParseOptions::default()
+        };
+        let tokenizer = Tokenizer::with_options(input, options);
+        let mut chars = input.char_indices().peekable();
+
+        match tokenizer.try_tokenize_string(&mut chars) {
+            Ok(result) => assert_eq!(result, r"\ud83d \ude00"),
+            Err(e) => panic!("Expect success tokenize string: {}", e),
+        }
+    }
+
+    #[test]
+    fn test_preserved_surrogate_is_written_as_text() {
+        let options = ParseOptions {
+            lone_surrogates: SurrogatePolicy::Preserve,
+            ..ParseOptions::default()
+        };
+        let preserved = crate::parse_json_with(r#""\ud800""#, &options).unwrap();
+        let escaped_backslash = crate::parse_json_with(r#""\\ud800""#, &options).unwrap();
+
+        assert_eq!(preserved, escaped_backslash);
+        assert_eq!(preserved.to_string(), r#""\\ud800""#);
+    }
+
+    #[test]
+    fn test_tokenize_json_spans() {
+        let input = "{\"a\": [true, -1.5]}";
+        let tokenizer = Tokenizer::new(input);
+
+        match tokenizer.tokenize_json() {
+            Ok(result) => {
+                let spans: Vec<Span> = result.into_iter().map(|(_, span)| span).collect();
+                assert_eq!(
+                    spans,
+                    vec![
+                        0..1,
+                        1..4,
+                        4..5,
+                        6..7,
+                        7..11,
+                        11..12,
+                        13..17,
+                        17..18,
+                        18..19
+                    ]
+                );
+            }
+            Err(e) => panic!("should not throw this error: {:?}", e),
+        }
+    }
+
+    #[test]
+    fn test_tokenize_json_error_spans() {
+        let cases = [
+            ("[1, nul]", ErrorKind::InvalidLiteral, 4..7),
+            ("[1, 'a']", ErrorKind::UnexpectedCharacter, 4..5),
+            ("[\"ab\\q\"]", ErrorKind::InvalidEscape, 4..6),
+            ("[\"abc", ErrorKind::UnterminatedString, 1..5),
+            ("[01]", ErrorKind::InvalidNumber, 1..3),
+            ("[1e999]", ErrorKind::NumberOverflow, 1..6),
+        ];
+
+        for (input, kind, span) in cases {
+            match Tokenizer::new(input).tokenize_json() {
+                Ok(_) => panic!("Expect error for {}", input),
+                Err(err) => {
+                    assert_eq!(err.kind(), kind, "{}", input);
+                    assert_eq!(err.span(), span, "{}", input);
+                }
+            }
+        }
+    }
 }
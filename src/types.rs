@@ -1,26 +1,549 @@
-use std::collections::HashMap;
+use std::{
+    cmp::Ordering,
+    fmt,
+    ops::{Index, IndexMut},
+};
 
+use crate::{
+    decimal::Decimal,
+    error::ParseError,
+    float::{parse_float, write_float},
+    map::{Entry, Map},
+};
+
+/// A JSON number.
+///
+/// Integers are kept exactly: anything in the `i64` range is an `Int`, and only integers above
+/// `i64::MAX` that still fit in a `u64` are a `UInt`, so each integer has exactly one
+/// representation. Numbers with a fraction or exponent, and integers outside both 64-bit ranges,
+/// are a `Float` rounded to the nearest `f64`.
+///
+/// When parsing with `NumberMode::Exact`, every number is a `Decimal` instead.
 #[derive(Debug, Clone, PartialEq)]
 pub enum Number {
     Float(f64),
-    Int(i32),
+    Int(i64),
+    UInt(u64),
+    Decimal(Decimal),
 }
 
+/// 2^63 and 2^64 as floats, the first values outside the `i64` and `u64` ranges.
+const I64_LIMIT: f64 = 9_223_372_036_854_775_808.0;
+const U64_LIMIT: f64 = 18_446_744_073_709_551_616.0;
+
+impl Number {
+    /// Returns the number as an `i64` if that conversion is exact, `None` otherwise.
+    pub fn as_i64(&self) -> Option<i64> {
+        match *self {
+            Number::Int(i) => Some(i),
+            Number::UInt(u) => i64::try_from(u).ok(),
+            Number::Float(f) if f.fract() == 0.0 && (-I64_LIMIT..I64_LIMIT).contains(&f) => {
+                Some(f as i64)
+            }
+            Number::Float(_) => None,
+            Number::Decimal(ref d) => d.to_i64().ok(),
+        }
+    }
+
+    /// Returns the number as a `u64` if that conversion is exact, `None` otherwise.
+    pub fn as_u64(&self) -> Option<u64> {
+        match *self {
+            Number::Int(i) => u64::try_from(i).ok(),
+            Number::UInt(u) => Some(u),
+            Number::Float(f) if f.fract() == 0.0 && (0.0..U64_LIMIT).contains(&f) => Some(f as u64),
+            Number::Float(_) => None,
+            Number::Decimal(ref d) => d.to_u64().ok(),
+        }
+    }
+
+    /// Returns the number as an `f64` if that conversion is exact, `None` for integers that
+    /// would be rounded. Use [`Number::as_f64_lossy`] to accept the rounding.
+    pub fn as_f64(&self) -> Option<f64> {
+        let f = self.as_f64_lossy();
+
+        match *self {
+            Number::Float(_) => Some(f),
+            Number::Decimal(ref d) => d.to_f64_exact().ok(),
+            // casting back saturates at the limits, so those must be excluded explicitly
+            Number::Int(i) if f < I64_LIMIT && f as i64 == i => Some(f),
+            Number::UInt(u) if f < U64_LIMIT && f as u64 == u => Some(f),
+            _ => None,
+        }
+    }
+
+    /// Returns the nearest `f64`, which may round large integers.
+    ///
+    /// A `Decimal` beyond the `f64` range becomes an infinity.
+    pub fn as_f64_lossy(&self) -> f64 {
+        match *self {
+            Number::Float(f) => f,
+            Number::Int(i) => i as f64,
+            Number::UInt(u) => u as f64,
+            Number::Decimal(ref d) => parse_float(d.as_str()),
+        }
+    }
+
+    pub fn is_integer(&self) -> bool {
+        match self {
+            Number::Int(_) | Number::UInt(_) => true,
+            Number::Float(_) => false,
+            Number::Decimal(d) => d.is_integer(),
+        }
+    }
+
+    /// Compares numeric values, so that `1` and `1.0` are equal; `None` if either is NaN.
+    ///
+    /// When either side is a `Decimal`, the comparison is exact, so decimals that only differ
+    /// beyond `f64` precision are not equal.
+    pub(crate) fn value_cmp(&self, other: &Number) -> Option<Ordering> {
+        if matches!(self, Number::Decimal(_)) || matches!(other, Number::Decimal(_)) {
+            return Some(self.to_decimal()?.cmp(&other.to_decimal()?));
+        }
+
+        let exact = |n: &Number| match *n {
+            Number::Int(i) => Some(i128::from(i)),
+            Number::UInt(u) => Some(i128::from(u)),
+            _ => None,
+        };
+
+        match (exact(self), exact(other)) {
+            (Some(left), Some(right)) => Some(left.cmp(&right)),
+            (Some(left), None) => cmp_integer_float(left, other.as_f64_lossy()),
+            (None, Some(right)) => {
+                cmp_integer_float(right, self.as_f64_lossy()).map(Ordering::reverse)
+            }
+            (None, None) => self.as_f64_lossy().partial_cmp(&other.as_f64_lossy()),
+        }
+    }
+
+    /// The number as a `Decimal`, a float as the shortest text that reads back as it. `None` for
+    /// non-finite floats.
+    fn to_decimal(&self) -> Option<Decimal> {
+        match self {
+            Number::Decimal(d) => Some(d.clone()),
+            Number::Float(f) if !f.is_finite() => None,
+            _ => Some(Decimal::from_lexeme(&self.to_string())),
+        }
+    }
+}
+
+/// Compares an integer with a float exactly, where converting the integer to `f64` could round
+/// it, e.g. 2^53 + 1 to 2^53.
+fn cmp_integer_float(integer: i128, float: f64) -> Option<Ordering> {
+    /// 2^127, beyond every `i64` and `u64`.
+    const LIMIT: f64 = 170_141_183_460_469_231_731_687_303_715_884_105_728.0;
+
+    let floor = float.floor();
+    match floor {
+        _ if float.is_nan() => None,
+        _ if floor >= LIMIT => Some(Ordering::Less),
+        _ if floor < -LIMIT => Some(Ordering::Greater),
+        // the float is an integer, or lies above its floor
+        _ => match integer.cmp(&(floor as i128)) {
+            Ordering::Equal if float > floor => Some(Ordering::Less),
+            ordering => Some(ordering),
+        },
+    }
+}
+
+macro_rules! number_from_signed {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Number {
+            fn from(i: $t) -> Self {
+                Number::Int(i as i64)
+            }
+        }
+    )*};
+}
+
+macro_rules! number_from_unsigned {
+    ($($t:ty)*) => {$(
+        impl From<$t> for Number {
+            /// Keeps values up to `i64::MAX` as an `Int`, like the parser does.
+            fn from(u: $t) -> Self {
+                match i64::try_from(u) {
+                    Ok(i) => Number::Int(i),
+                    Err(_) => Number::UInt(u as u64),
+                }
+            }
+        }
+    )*};
+}
+
+number_from_signed!(i8 i16 i32 i64 isize);
+number_from_unsigned!(u8 u16 u32 u64 usize);
+
+impl From<f64> for Number {
+    fn from(f: f64) -> Self {
+        Number::Float(f)
+    }
+}
+
+impl From<f32> for Number {
+    fn from(f: f32) -> Self {
+        Number::Float(f as f64)
+    }
+}
+
+impl From<Decimal> for Number {
+    fn from(d: Decimal) -> Self {
+        Number::Decimal(d)
+    }
+}
+
+/// Writes the number as JSON text.
+///
+/// Floats use the shortest representation that reads back as the same `f64`, e.g. `0.1`, and
+/// non-finite floats are written as `null`. A `Decimal` is written exactly as it was parsed.
+impl fmt::Display for Number {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Number::Float(float) => write_float(f, *float),
+            Number::Int(i) => write!(f, "{}", i),
+            Number::UInt(u) => write!(f, "{}", u),
+            Number::Decimal(d) => write!(f, "{}", d),
+        }
+    }
+}
+
+/// Any JSON value. Arrays and objects nest further values.
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonValue {
     Null,
     Number(Number),
     String(String),
     Boolean(bool),
-    Document(Box<JsonDocument>),
+    Array(Vec<JsonValue>),
+    Object(Map),
 }
 
+/// Returned by indexing when a key or index is missing, so lookups can be chained.
+static NULL: JsonValue = JsonValue::Null;
+
+impl JsonValue {
+    /// Looks up a member of an object. `None` if the key is missing or this is not an object.
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object().and_then(|obj| obj.get(key))
+    }
+
+    /// Looks up an element of an array. `None` if the index is out of bounds or this is not an
+    /// array.
+    pub fn get_index(&self, index: usize) -> Option<&JsonValue> {
+        self.as_array().and_then(|arr| arr.get(index))
+    }
+
+    pub fn is_null(&self) -> bool {
+        matches!(self, JsonValue::Null)
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Boolean(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_number(&self) -> Option<&Number> {
+        match self {
+            JsonValue::Number(n) => Some(n),
+            _ => None,
+        }
+    }
+
+    /// The number as an `i64`, see [`Number::as_i64`].
+    pub fn as_i64(&self) -> Option<i64> {
+        self.as_number().and_then(Number::as_i64)
+    }
+
+    /// The number as a `u64`, see [`Number::as_u64`].
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number().and_then(Number::as_u64)
+    }
+
+    /// The number as an `f64`, see [`Number::as_f64`].
+    pub fn as_f64(&self) -> Option<f64> {
+        self.as_number().and_then(Number::as_f64)
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&Map> {
+        match self {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn as_array_mut(&mut self) -> Option<&mut Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(arr) => Some(arr),
+            _ => None,
+        }
+    }
+
+    pub fn as_object_mut(&mut self) -> Option<&mut Map> {
+        match self {
+            JsonValue::Object(obj) => Some(obj),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut JsonValue> {
+        self.as_object_mut().and_then(|obj| obj.get_mut(key))
+    }
+
+    pub fn get_index_mut(&mut self, index: usize) -> Option<&mut JsonValue> {
+        self.as_array_mut().and_then(|arr| arr.get_mut(index))
+    }
+
+    /// Inserts a member into an object, returning the previous value of `key`.
+    ///
+    /// `Null` is turned into an empty object first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is neither an object nor `Null`.
+    pub fn insert(&mut self, key: impl Into<String>, value: JsonValue) -> Option<JsonValue> {
+        self.object_or_upgrade().insert(key, value)
+    }
+
+    /// Gets the entry for `key` in an object, to inspect or modify it in place.
+    ///
+    /// `Null` is turned into an empty object first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is neither an object nor `Null`.
+    pub fn entry(&mut self, key: impl Into<String>) -> Entry<'_> {
+        self.object_or_upgrade().entry(key)
+    }
+
+    /// Removes a member from an object. `None` if the key is missing or this is not an object.
+    pub fn remove(&mut self, key: &str) -> Option<JsonValue> {
+        self.as_object_mut().and_then(|obj| obj.remove(key))
+    }
+
+    /// Appends an element to an array.
+    ///
+    /// `Null` is turned into an empty array first.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the value is neither an array nor `Null`.
+    pub fn push(&mut self, value: JsonValue) {
+        if self.is_null() {
+            *self = JsonValue::Array(Vec::new());
+        }
+        match self {
+            JsonValue::Array(arr) => arr.push(value),
+            other => panic!("cannot push onto {}", other.type_name()),
+        }
+    }
+
+    /// Removes an element from an array, shifting the following ones. `None` if the index is out
+    /// of bounds or this is not an array.
+    pub fn remove_index(&mut self, index: usize) -> Option<JsonValue> {
+        match self.as_array_mut() {
+            Some(arr) if index < arr.len() => Some(arr.remove(index)),
+            _ => None,
+        }
+    }
+
+    /// Takes the value out, leaving `Null` in its place.
+    pub fn take(&mut self) -> JsonValue {
+        std::mem::replace(self, JsonValue::Null)
+    }
+
+    /// Deep equality where numbers compare by value, as JSONPath and JSON Patch require, unlike
+    /// `==` which tells `1` and `1.0` apart.
+    pub(crate) fn value_eq(&self, other: &JsonValue) -> bool {
+        match (self, other) {
+            (JsonValue::Number(left), JsonValue::Number(right)) => {
+                left.value_cmp(right) == Some(Ordering::Equal)
+            }
+            (JsonValue::Array(left), JsonValue::Array(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .zip(right)
+                        .all(|(left, right)| left.value_eq(right))
+            }
+            (JsonValue::Object(left), JsonValue::Object(right)) => {
+                left.len() == right.len()
+                    && left
+                        .iter()
+                        .all(|(key, left)| right.get(key).is_some_and(|right| left.value_eq(right)))
+            }
+            _ => self == other,
+        }
+    }
+
+    /// The JSON type of the value with an article, e.g. `"an object"`, for messages.
+    pub(crate) fn type_name(&self) -> &'static str {
+        match self {
+            JsonValue::Null => "null",
+            JsonValue::Number(_) => "a number",
+            JsonValue::String(_) => "a string",
+            JsonValue::Boolean(_) => "a boolean",
+            JsonValue::Array(_) => "an array",
+            JsonValue::Object(_) => "an object",
+        }
+    }
+
+    fn object_or_upgrade(&mut self) -> &mut Map {
+        if self.is_null() {
+            *self = JsonValue::Object(Map::new());
+        }
+        match self {
+            JsonValue::Object(obj) => obj,
+            other => panic!("cannot insert a key into {}", other.type_name()),
+        }
+    }
+}
+
+macro_rules! value_from_number {
+    ($($t:ty)*) => {$(
+        impl From<$t> for JsonValue {
+            fn from(n: $t) -> Self {
+                JsonValue::Number(Number::from(n))
+            }
+        }
+    )*};
+}
+
+value_from_number!(i8 i16 i32 i64 isize u8 u16 u32 u64 usize f32 f64 Decimal);
+
+impl From<Number> for JsonValue {
+    fn from(n: Number) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Boolean(b)
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl From<&String> for JsonValue {
+    fn from(s: &String) -> Self {
+        JsonValue::String(s.clone())
+    }
+}
+
+impl From<Map> for JsonValue {
+    fn from(obj: Map) -> Self {
+        JsonValue::Object(obj)
+    }
+}
+
+impl<T: Into<JsonValue>> From<Vec<T>> for JsonValue {
+    fn from(arr: Vec<T>) -> Self {
+        JsonValue::Array(arr.into_iter().map(Into::into).collect())
+    }
+}
+
+/// `None` becomes `Null`.
+impl<T: Into<JsonValue>> From<Option<T>> for JsonValue {
+    fn from(option: Option<T>) -> Self {
+        option.map_or(JsonValue::Null, Into::into)
+    }
+}
+
+impl<T: Into<JsonValue>> FromIterator<T> for JsonValue {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        JsonValue::Array(iter.into_iter().map(Into::into).collect())
+    }
+}
+
+/// Looks up a member of an object, returning `JsonValue::Null` if the key is missing or this is
+/// not an object, so that `value["users"][0]["name"]` never panics.
+impl Index<&str> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        self.get(key).unwrap_or(&NULL)
+    }
+}
+
+/// Looks up an element of an array, returning `JsonValue::Null` if the index is out of bounds or
+/// this is not an array.
+impl Index<usize> for JsonValue {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        self.get_index(index).unwrap_or(&NULL)
+    }
+}
+
+/// Gets a mutable reference to a member of an object, inserting `Null` if the key is missing, so
+/// that `value["meta"]["trace_id"] = ...` creates the path.
+///
+/// # Panics
+///
+/// Panics if the value is neither an object nor `Null`; `Null` is turned into an empty object.
+impl IndexMut<&str> for JsonValue {
+    fn index_mut(&mut self, key: &str) -> &mut JsonValue {
+        self.entry(key).or_insert(JsonValue::Null)
+    }
+}
+
+/// Gets a mutable reference to an element of an array.
+///
+/// # Panics
+///
+/// Panics if the value is not an array or the index is out of bounds.
+impl IndexMut<usize> for JsonValue {
+    fn index_mut(&mut self, index: usize) -> &mut JsonValue {
+        let type_name = self.type_name();
+        match self {
+            JsonValue::Array(arr) => {
+                let len = arr.len();
+                arr.get_mut(index).unwrap_or_else(|| {
+                    panic!("index {} out of bounds for array of length {}", index, len)
+                })
+            }
+            _ => panic!("cannot index into {} with a position", type_name),
+        }
+    }
+}
+
+/// The root of a JSON text, back when it had to be an object or an array.
+///
+/// [`JsonValue`] now represents arrays and objects directly. This type only remains to ease
+/// migration, through `From<JsonDocument> for JsonValue` and `TryFrom<JsonValue>`.
+#[deprecated(note = "use `JsonValue`, which has `Array` and `Object` variants")]
 #[derive(Debug, Clone, PartialEq)]
 pub enum JsonDocument {
     Array(Vec<JsonValue>),
-    Object(HashMap<String, JsonValue>),
+    Object(Map),
 }
 
+#[allow(deprecated)]
 impl JsonDocument {
     pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
         match self {
@@ -29,10 +552,221 @@ impl JsonDocument {
         }
     }
 
-    pub fn as_object(&self) -> Option<&HashMap<String, JsonValue>> {
+    pub fn as_object(&self) -> Option<&Map> {
         match self {
             JsonDocument::Object(obj) => Some(obj),
             _ => None,
         }
     }
 }
+
+#[allow(deprecated)]
+impl Index<&str> for JsonDocument {
+    type Output = JsonValue;
+
+    fn index(&self, key: &str) -> &JsonValue {
+        self.as_object()
+            .and_then(|obj| obj.get(key))
+            .unwrap_or(&NULL)
+    }
+}
+
+#[allow(deprecated)]
+impl Index<usize> for JsonDocument {
+    type Output = JsonValue;
+
+    fn index(&self, index: usize) -> &JsonValue {
+        self.as_array()
+            .and_then(|arr| arr.get(index))
+            .unwrap_or(&NULL)
+    }
+}
+
+#[allow(deprecated)]
+impl From<JsonDocument> for JsonValue {
+    fn from(document: JsonDocument) -> Self {
+        match document {
+            JsonDocument::Array(arr) => JsonValue::Array(arr),
+            JsonDocument::Object(obj) => JsonValue::Object(obj),
+        }
+    }
+}
+
+/// Fails with the value itself when it is neither an array nor an object.
+#[allow(deprecated)]
+impl TryFrom<JsonValue> for JsonDocument {
+    type Error = JsonValue;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        match value {
+            JsonValue::Array(arr) => Ok(JsonDocument::Array(arr)),
+            JsonValue::Object(obj) => Ok(JsonDocument::Object(obj)),
+            other => Err(other),
+        }
+    }
+}
+
+/// The outcome of a recovering parse: a best-effort document and every error found on the way.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecoveredDocument {
+    /// The partially parsed document, with `JsonValue::Null` in place of values that could not be
+    /// read. `None` when no value could be recovered at the root.
+    pub document: Option<JsonValue>,
+    /// All errors found, ordered by their position in the input.
+    pub errors: Vec<ParseError>,
+}
+
+impl RecoveredDocument {
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_number_as_i64() {
+        assert_eq!(Number::Int(-7).as_i64(), Some(-7));
+        assert_eq!(Number::UInt(u64::MAX).as_i64(), None);
+        assert_eq!(Number::Float(3.0).as_i64(), Some(3));
+        assert_eq!(Number::Float(3.5).as_i64(), None);
+        assert_eq!(
+            Number::Float(-9223372036854775808.0).as_i64(),
+            Some(i64::MIN)
+        );
+        assert_eq!(Number::Float(9223372036854775808.0).as_i64(), None);
+    }
+
+    #[test]
+    fn test_number_as_u64() {
+        assert_eq!(Number::Int(7).as_u64(), Some(7));
+        assert_eq!(Number::Int(-1).as_u64(), None);
+        assert_eq!(Number::UInt(u64::MAX).as_u64(), Some(u64::MAX));
+        assert_eq!(Number::Float(18446744073709551616.0).as_u64(), None);
+    }
+
+    #[test]
+    fn test_number_as_f64_reports_lossy_conversion() {
+        assert_eq!(Number::Int(1 << 53).as_f64(), Some(9007199254740992.0));
+        assert_eq!(Number::Int((1 << 53) + 1).as_f64(), None);
+        assert_eq!(Number::Int(i64::MAX).as_f64(), None);
+        assert_eq!(Number::Int(i64::MIN).as_f64(), Some(-9223372036854775808.0));
+        assert_eq!(Number::UInt(u64::MAX).as_f64(), None);
+        assert_eq!(
+            Number::UInt(u64::MAX).as_f64_lossy(),
+            18446744073709551616.0
+        );
+        assert_eq!(Number::Float(0.5).as_f64(), Some(0.5));
+    }
+
+    #[test]
+    fn test_number_display() {
+        assert_eq!(Number::Float(0.1).to_string(), "0.1");
+        assert_eq!(Number::Float(2.0).to_string(), "2.0");
+        assert_eq!(Number::Float(f64::NAN).to_string(), "null");
+        assert_eq!(Number::Int(-42).to_string(), "-42");
+        assert_eq!(Number::UInt(u64::MAX).to_string(), "18446744073709551615");
+        let decimal = "1.50E+3".parse().unwrap();
+        assert_eq!(Number::Decimal(decimal).to_string(), "1.50E+3");
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_json_document_conversions() {
+        let document = JsonDocument::Array(vec![JsonValue::Null]);
+        let value = JsonValue::from(document.clone());
+
+        assert_eq!(value, JsonValue::Array(vec![JsonValue::Null]));
+        assert_eq!(JsonDocument::try_from(value), Ok(document));
+        assert_eq!(
+            JsonDocument::try_from(JsonValue::Boolean(true)),
+            Err(JsonValue::Boolean(true))
+        );
+    }
+
+    #[test]
+    fn test_json_value_chained_indexing() {
+        let value = crate::parse_json(
+            r#"{"users": [{"name": "Ada", "admin": true, "id": 7}], "ratio": 0.5}"#,
+        )
+        .unwrap();
+
+        assert_eq!(value["users"][0]["name"].as_str(), Some("Ada"));
+        assert_eq!(value["users"][0]["admin"].as_bool(), Some(true));
+        assert_eq!(value["users"][0]["id"].as_i64(), Some(7));
+        assert_eq!(value["users"][0]["id"].as_f64(), Some(7.0));
+        assert_eq!(value["ratio"].as_f64(), Some(0.5));
+        assert_eq!(value["users"].as_array().map(Vec::len), Some(1));
+        assert!(value.as_object().is_some());
+    }
+
+    #[test]
+    fn test_json_value_missing_lookups_are_null() {
+        let value = crate::parse_json(r#"{"users": [], "name": "nail"}"#).unwrap();
+
+        assert!(value["missing"].is_null());
+        assert!(value["users"][3]["name"].is_null());
+        assert!(value["name"]["first"].is_null());
+        assert!(value[0].is_null());
+        assert_eq!(value.get("missing"), None);
+        assert_eq!(value.get_index(0), None);
+        assert_eq!(value["name"].as_i64(), None);
+        assert_eq!(value.get("name").and_then(JsonValue::as_str), Some("nail"));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn test_json_document_indexing() {
+        let document = JsonDocument::Array(vec![JsonValue::String("a".to_string())]);
+
+        assert_eq!(document[0].as_str(), Some("a"));
+        assert!(document[1].is_null());
+        assert!(document["key"].is_null());
+    }
+
+    #[test]
+    fn test_json_value_mutation() {
+        let mut value = crate::parse_json(r#"{"user": {"name": "Ada"}, "tags": ["a"]}"#).unwrap();
+
+        value["user"]["id"] = JsonValue::Number(Number::Int(7));
+        value["trace"]["span"] = JsonValue::String("x".to_string());
+        value["tags"].push(JsonValue::String("b".to_string()));
+        value["tags"][0] = JsonValue::String("z".to_string());
+        assert_eq!(value.insert("ok", JsonValue::Boolean(true)), None);
+        *value
+            .entry("count")
+            .or_insert(JsonValue::Number(Number::Int(0))) = JsonValue::Number(Number::Int(1));
+
+        let expected = crate::parse_json(
+            r#"{"user": {"name": "Ada", "id": 7}, "tags": ["z", "b"], "trace": {"span": "x"}, "ok": true, "count": 1}"#,
+        )
+        .unwrap();
+        assert_eq!(value, expected);
+
+        assert_eq!(value.remove("ok"), Some(JsonValue::Boolean(true)));
+        assert_eq!(
+            value["tags"].remove_index(0),
+            Some(JsonValue::String("z".to_string()))
+        );
+        assert_eq!(value["tags"].remove_index(5), None);
+        let user = value["user"].take();
+        assert_eq!(user["name"].as_str(), Some("Ada"));
+        assert!(value["user"].is_null());
+        if let Some(name) = value
+            .get_mut("trace")
+            .and_then(|trace| trace.get_mut("span"))
+        {
+            *name = JsonValue::Null;
+        }
+        assert!(value["trace"]["span"].is_null());
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot insert a key into a string")]
+    fn test_json_value_insert_into_scalar_panics() {
+        let mut value = JsonValue::String("abc".to_string());
+        value.insert("key", JsonValue::Null);
+    }
+}
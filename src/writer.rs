@@ -0,0 +1,477 @@
+use std::{error::Error, fmt, io};
+
+use crate::{
+    options::WriteOptions,
+    serializer::{measure, write_string, Serializer, KEY_COLOR},
+    types::JsonValue,
+};
+
+/// Why a [`JsonWriter`] call failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriterErrorKind {
+    /// The call does not fit where the document is, such as a value where an object key is
+    /// expected, or the end of a container that is not the innermost open one.
+    Nesting,
+    /// `finish` was called before the root value was complete.
+    Incomplete,
+    /// The underlying writer failed.
+    Io,
+}
+
+/// Error returned by [`JsonWriter`]. A call that fails with `Nesting` writes nothing, so the
+/// document can still be completed.
+#[derive(Debug)]
+pub struct WriterError {
+    kind: WriterErrorKind,
+    message: String,
+    io: Option<io::Error>,
+}
+
+impl WriterError {
+    fn new(kind: WriterErrorKind, message: impl Into<String>) -> Self {
+        WriterError {
+            kind,
+            message: message.into(),
+            io: None,
+        }
+    }
+
+    fn nesting(message: impl Into<String>) -> Self {
+        WriterError::new(WriterErrorKind::Nesting, message)
+    }
+
+    fn io(error: io::Error) -> Self {
+        WriterError {
+            io: Some(error),
+            ..WriterError::new(WriterErrorKind::Io, "failed to write output")
+        }
+    }
+
+    pub fn kind(&self) -> WriterErrorKind {
+        self.kind
+    }
+}
+
+impl fmt::Display for WriterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.io {
+            Some(error) => write!(f, "{}: {}", self.message, error),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl Error for WriterError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.io.as_ref().map(|error| error as _)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Container {
+    Array,
+    Object,
+}
+
+impl Container {
+    fn name(self) -> &'static str {
+        match self {
+            Container::Array => "array",
+            Container::Object => "object",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Frame {
+    container: Container,
+    len: usize,
+    /// In an object, whether a key was written that still waits for its value.
+    has_key: bool,
+}
+
+/// Writes one JSON document into an `io::Write` call by call, without building it in memory.
+///
+/// Containers are opened and closed with `begin_*` and `end_*`, objects take a `key` before
+/// each value, and `value` writes a whole [`JsonValue`] at once. Calls that would produce
+/// invalid JSON fail with [`WriterErrorKind::Nesting`], and [`JsonWriter::finish`] checks that
+/// the document is complete.
+///
+/// Each call hands its output to the underlying writer before returning, so wrapping an
+/// unbuffered writer in a `BufWriter` saves many small writes. The writer follows the
+/// indentation, separators, escaping and number format of its [`WriteOptions`]; key order and
+/// `max_width` only apply within the values passed to `value`.
+#[derive(Debug)]
+pub struct JsonWriter<W: io::Write> {
+    writer: W,
+    options: WriteOptions,
+    stack: Vec<Frame>,
+    /// Set once the root value has been written completely.
+    complete: bool,
+    /// Output of the current call, handed to the writer at its end.
+    buffer: String,
+    /// Columns taken by the key that was just written, so that values respect `max_width`.
+    key_width: usize,
+}
+
+impl<W: io::Write> JsonWriter<W> {
+    /// A writer producing compact JSON.
+    pub fn new(writer: W) -> Self {
+        JsonWriter::with_options(writer, WriteOptions::default())
+    }
+
+    /// A writer producing indented JSON, see [`WriteOptions::pretty`].
+    pub fn pretty(writer: W) -> Self {
+        JsonWriter::with_options(writer, WriteOptions::pretty())
+    }
+
+    pub fn with_options(writer: W, options: WriteOptions) -> Self {
+        JsonWriter {
+            writer,
+            options,
+            stack: Vec::new(),
+            complete: false,
+            buffer: String::new(),
+            key_width: 0,
+        }
+    }
+
+    pub fn begin_object(&mut self) -> Result<(), WriterError> {
+        self.begin(Container::Object)
+    }
+
+    pub fn end_object(&mut self) -> Result<(), WriterError> {
+        self.end(Container::Object)
+    }
+
+    pub fn begin_array(&mut self) -> Result<(), WriterError> {
+        self.begin(Container::Array)
+    }
+
+    pub fn end_array(&mut self) -> Result<(), WriterError> {
+        self.end(Container::Array)
+    }
+
+    /// Writes the key of the next object member.
+    pub fn key(&mut self, key: &str) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(frame) if frame.container == Container::Object && !frame.has_key => {}
+            Some(frame) if frame.container == Container::Object => {
+                return Err(WriterError::nesting(format!(
+                    "expected a value, found key {:?}",
+                    key
+                )))
+            }
+            _ => {
+                return Err(WriterError::nesting(format!(
+                    "key {:?} outside of an object",
+                    key
+                )))
+            }
+        }
+
+        self.separate();
+        let escaping = self.options.escaping;
+        let mut serializer = Serializer::new(&mut self.buffer, &self.options);
+        let _ = serializer.painted(KEY_COLOR, |out| write_string(out, key, escaping));
+        self.buffer.push_str(&self.options.key_separator);
+        self.key_width = measure(|out| write_string(out, key, escaping))
+            + self.options.key_separator.chars().count();
+
+        if let Some(frame) = self.stack.last_mut() {
+            frame.has_key = true;
+        }
+        self.emit()
+    }
+
+    /// Writes a whole value: the root, an array element or the value of the last key.
+    pub fn value(&mut self, value: &JsonValue) -> Result<(), WriterError> {
+        self.start_value("a value")?;
+
+        let depth = self.stack.len();
+        let mut serializer = Serializer::new(&mut self.buffer, &self.options);
+        let reserved = serializer.indent_width(depth)
+            + self.key_width
+            + self.options.item_separator.trim_end().chars().count();
+        let _ = serializer.value(value, depth, reserved);
+
+        self.complete = self.stack.is_empty();
+        self.emit()
+    }
+
+    /// Flushes the underlying writer.
+    pub fn flush(&mut self) -> Result<(), WriterError> {
+        self.writer.flush().map_err(WriterError::io)
+    }
+
+    /// The underlying writer, e.g. to check how much has been written so far.
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Checks that the document is complete, flushes the output and returns the underlying
+    /// writer.
+    pub fn finish(mut self) -> Result<W, WriterError> {
+        if let Some(frame) = self.stack.last() {
+            return Err(WriterError::new(
+                WriterErrorKind::Incomplete,
+                format!(
+                    "{} still open, innermost an {}",
+                    match self.stack.len() {
+                        1 => "1 container".to_string(),
+                        open => format!("{} containers", open),
+                    },
+                    frame.container.name()
+                ),
+            ));
+        }
+        if !self.complete {
+            return Err(WriterError::new(
+                WriterErrorKind::Incomplete,
+                "no value was written",
+            ));
+        }
+
+        self.flush()?;
+        Ok(self.writer)
+    }
+
+    fn begin(&mut self, container: Container) -> Result<(), WriterError> {
+        self.start_value(&format!("the start of an {}", container.name()))?;
+
+        self.buffer.push(match container {
+            Container::Array => '[',
+            Container::Object => '{',
+        });
+        self.stack.push(Frame {
+            container,
+            len: 0,
+            has_key: false,
+        });
+        self.emit()
+    }
+
+    fn end(&mut self, container: Container) -> Result<(), WriterError> {
+        match self.stack.last() {
+            Some(frame) if frame.container == container && !frame.has_key => {}
+            Some(frame) if frame.container == container => {
+                return Err(WriterError::nesting(
+                    "expected a value for the last key, found the end of the object",
+                ))
+            }
+            Some(frame) => {
+                return Err(WriterError::nesting(format!(
+                    "cannot end an {} inside an {}",
+                    container.name(),
+                    frame.container.name()
+                )))
+            }
+            None => {
+                return Err(WriterError::nesting(format!(
+                    "cannot end an {} that was not begun",
+                    container.name()
+                )))
+            }
+        }
+
+        let frame = self.stack.pop();
+        if frame.is_some_and(|frame| frame.len > 0) && self.options.indent.is_some() {
+            let depth = self.stack.len();
+            let _ = Serializer::new(&mut self.buffer, &self.options).newline(depth);
+        }
+        self.buffer.push(match container {
+            Container::Array => ']',
+            Container::Object => '}',
+        });
+
+        self.complete = self.stack.is_empty();
+        self.emit()
+    }
+
+    /// Checks that a value may come next, and writes the separator and indentation before it.
+    fn start_value(&mut self, found: &str) -> Result<(), WriterError> {
+        match self.stack.last_mut() {
+            None if self.complete => Err(WriterError::nesting(format!(
+                "found {} after the end of the document",
+                found
+            ))),
+            None => Ok(()),
+            Some(frame) if frame.container == Container::Object && !frame.has_key => {
+                Err(WriterError::nesting(format!(
+                    "expected a key or the end of the object, found {}",
+                    found
+                )))
+            }
+            Some(frame) if frame.container == Container::Object => {
+                frame.has_key = false;
+                Ok(())
+            }
+            Some(_) => {
+                self.key_width = 0;
+                self.separate();
+                Ok(())
+            }
+        }
+    }
+
+    /// Writes what goes before the next element or member of the innermost container.
+    fn separate(&mut self) {
+        let depth = self.stack.len();
+        let pretty = self.options.indent.is_some();
+        let Some(frame) = self.stack.last_mut() else {
+            return;
+        };
+
+        if frame.len > 0 {
+            match pretty {
+                true => self.buffer.push_str(self.options.item_separator.trim_end()),
+                false => self.buffer.push_str(&self.options.item_separator),
+            }
+        }
+        frame.len += 1;
+        if pretty {
+            let _ = Serializer::new(&mut self.buffer, &self.options).newline(depth);
+        }
+    }
+
+    /// Hands the output of the current call to the writer.
+    fn emit(&mut self) -> Result<(), WriterError> {
+        let result = self.writer.write_all(self.buffer.as_bytes());
+        self.buffer.clear();
+        result.map_err(WriterError::io)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json;
+
+    fn text(writer: JsonWriter<Vec<u8>>) -> String {
+        String::from_utf8(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_compact_document() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_object().unwrap();
+        writer.key("rows").unwrap();
+        writer.begin_array().unwrap();
+        for id in 0..3 {
+            writer.value(&json!({"id": id})).unwrap();
+        }
+        writer.end_array().unwrap();
+        writer.key("empty").unwrap();
+        writer.begin_object().unwrap();
+        writer.end_object().unwrap();
+        writer.key("done").unwrap();
+        writer.value(&json!(true)).unwrap();
+        writer.end_object().unwrap();
+
+        assert_eq!(
+            text(writer),
+            r#"{"rows":[{"id":0},{"id":1},{"id":2}],"empty":{},"done":true}"#
+        );
+    }
+
+    #[test]
+    fn test_pretty_matches_serializer() {
+        let expected = json!({"a": [1, {"b": [2, 3]}, []], "c": {"d": null}});
+
+        let mut writer = JsonWriter::pretty(Vec::new());
+        writer.begin_object().unwrap();
+        writer.key("a").unwrap();
+        writer.begin_array().unwrap();
+        writer.value(&json!(1)).unwrap();
+        writer.value(&json!({"b": [2, 3]})).unwrap();
+        writer.begin_array().unwrap();
+        writer.end_array().unwrap();
+        writer.end_array().unwrap();
+        writer.key("c").unwrap();
+        writer.value(&json!({"d": null})).unwrap();
+        writer.end_object().unwrap();
+
+        assert_eq!(text(writer), expected.to_string_pretty());
+    }
+
+    #[test]
+    fn test_output_is_written_incrementally() {
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        writer.value(&json!("a")).unwrap();
+        assert_eq!(writer.get_ref(), br#"["a""#);
+
+        writer.value(&json!(2)).unwrap();
+        assert_eq!(writer.get_ref(), br#"["a",2"#);
+    }
+
+    #[test]
+    fn test_nesting_errors() {
+        let mut writer = JsonWriter::new(Vec::new());
+        let kind = |result: Result<(), WriterError>| result.unwrap_err().kind();
+
+        assert_eq!(kind(writer.key("a")), WriterErrorKind::Nesting);
+        assert_eq!(kind(writer.end_array()), WriterErrorKind::Nesting);
+        writer.begin_object().unwrap();
+        let error = writer.value(&json!(1)).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "expected a key or the end of the object, found a value"
+        );
+        assert_eq!(kind(writer.end_array()), WriterErrorKind::Nesting);
+        writer.key("a").unwrap();
+        assert_eq!(kind(writer.key("b")), WriterErrorKind::Nesting);
+        assert_eq!(kind(writer.end_object()), WriterErrorKind::Nesting);
+        writer.begin_array().unwrap();
+        assert_eq!(kind(writer.key("c")), WriterErrorKind::Nesting);
+        writer.end_array().unwrap();
+        writer.end_object().unwrap();
+        assert_eq!(kind(writer.value(&json!(2))), WriterErrorKind::Nesting);
+
+        // the failed calls wrote nothing
+        assert_eq!(text(writer), r#"{"a":[]}"#);
+    }
+
+    #[test]
+    fn test_finish_requires_a_complete_document() {
+        let writer = JsonWriter::new(Vec::new());
+        let error = writer.finish().unwrap_err();
+        assert_eq!(error.kind(), WriterErrorKind::Incomplete);
+        assert_eq!(error.to_string(), "no value was written");
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.begin_array().unwrap();
+        writer.begin_object().unwrap();
+        let error = writer.finish().unwrap_err();
+        assert_eq!(error.kind(), WriterErrorKind::Incomplete);
+        assert_eq!(
+            error.to_string(),
+            "2 containers still open, innermost an object"
+        );
+
+        let mut writer = JsonWriter::new(Vec::new());
+        writer.value(&json!("root")).unwrap();
+        assert_eq!(text(writer), r#""root""#);
+    }
+
+    #[test]
+    fn test_io_errors() {
+        struct Broken;
+
+        impl io::Write for Broken {
+            fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let mut writer = JsonWriter::new(Broken);
+        let error = writer.begin_array().unwrap_err();
+        assert_eq!(error.kind(), WriterErrorKind::Io);
+        assert_eq!(error.to_string(), "failed to write output: closed");
+        assert!(error.source().is_some());
+    }
+}
@@ -0,0 +1,52 @@
+//! Runs the JSONTestSuite-style corpus in `tests/corpus`.
+//!
+//! Files prefixed with `y_` must be accepted, files prefixed with `n_` must be rejected and
+//! files prefixed with `i_` are implementation defined, so they only need to not panic.
+
+use std::{fs, path::Path};
+
+use nail::{parse_json, parse_json_recovering};
+
+fn parse_bytes(bytes: &[u8]) -> bool {
+    match std::str::from_utf8(bytes) {
+        Ok(input) => parse_json(input).is_ok(),
+        // a JSON text must be valid UTF-8
+        Err(_) => false,
+    }
+}
+
+#[test]
+fn test_json_test_suite_corpus() {
+    let corpus_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/corpus");
+    let mut failures = Vec::new();
+    let mut case_count = 0;
+
+    for entry in fs::read_dir(&corpus_dir).expect("corpus directory should exist") {
+        let path = entry.expect("corpus entry should be readable").path();
+        let name = path.file_name().unwrap().to_string_lossy().into_owned();
+        let bytes = fs::read(&path).expect("corpus file should be readable");
+        let accepted = parse_bytes(&bytes);
+        case_count += 1;
+
+        // the recovering parser must never find fewer problems than the strict one
+        if let Ok(input) = std::str::from_utf8(&bytes) {
+            if !accepted && parse_json_recovering(input).is_valid() {
+                failures.push(format!("{} should report errors when recovering", name));
+            }
+        }
+
+        match name.split('_').next() {
+            Some("y") if !accepted => failures.push(format!("{} should be accepted", name)),
+            Some("n") if accepted => failures.push(format!("{} should be rejected", name)),
+            Some("y" | "n" | "i") => {}
+            _ => panic!("unexpected corpus file name {}", name),
+        }
+    }
+
+    assert!(case_count > 0, "corpus should not be empty");
+    assert!(
+        failures.is_empty(),
+        "conformance failures:\n{}",
+        failures.join("\n")
+    );
+}